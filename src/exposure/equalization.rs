@@ -0,0 +1,248 @@
+use ndarray::{indices, Array, ArrayRef, Dimension, IxDyn};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{
+    round_ties_even,
+    threshold::global::{histogram, values},
+};
+
+/// Histogram equalization.
+///
+/// Each value is replaced by the normalized cumulative distribution of the values, linearly
+/// interpolated between the bin centers of the histogram. The output is in `[0, 1]` and its
+/// histogram is approximately flat.
+///
+/// * `data` - The input N-D data.
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to compute the histogram. The
+///   equalization is still applied to all voxels.
+///
+/// **Panics** if `nbins` is 0, if `mask` doesn't have the same shape as `data` or if there's no
+/// voxel to use.
+pub fn equalize_hist<A, D>(
+    data: &ArrayRef<A, D>,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    let (hist, centers) = histogram(&values(data, mask), nbins);
+    let mut cdf = hist;
+    for i in 1..cdf.len() {
+        cdf[i] += cdf[i - 1];
+    }
+    let total = cdf[cdf.len() - 1];
+    cdf.iter_mut().for_each(|c| *c /= total);
+
+    data.mapv(|v| {
+        let v = v.to_f64().unwrap();
+        let i = centers.partition_point(|&c| c <= v);
+        let equalized = if i == 0 {
+            cdf[0]
+        } else if i == centers.len() {
+            cdf[i - 1]
+        } else {
+            let t = (v - centers[i - 1]) / (centers[i] - centers[i - 1]);
+            cdf[i - 1] + t * (cdf[i] - cdf[i - 1])
+        };
+        A::from_f64(equalized).unwrap()
+    })
+}
+
+/// Contrast Limited Adaptive Histogram Equalization (CLAHE).
+///
+/// The image is divided in tiles of `kernel_size` voxels. A histogram equalization mapping is
+/// computed on each tile, with a histogram clipped at `clip_limit` to limit the amplification of
+/// the noise. The clipped counts are redistributed to all bins. Each voxel is then mapped with a
+/// multilinear interpolation (bilinear in 2D, trilinear in 3D) of the mappings of the closest
+/// tiles, to avoid visible tile boundaries. The image is extended by reflection when its shape
+/// isn't a multiple of `kernel_size`.
+///
+/// Like scikit-image, the values are quantized to `2^14` grey levels before the equalization and
+/// the output is rescaled to `[0, 1]`.
+///
+/// * `data` - The input N-D data, usually 2D or 3D.
+/// * `kernel_size` - Length of the tiles on each axis. A single value can be given for all axes.
+/// * `clip_limit` - Normalized clipping limit, in `[0, 1]`. The histograms are clipped at
+///   `clip_limit` times the number of voxels of a tile. Higher values give more contrast. `0`
+///   disables the clipping.
+/// * `nbins` - Number of bins of the histograms. Usually 256.
+///
+/// **Panics** if `nbins` is 0 or if `kernel_size` doesn't have 1 or `data.ndim()` non-zero values.
+pub fn equalize_adapthist<A, D>(
+    data: &ArrayRef<A, D>,
+    kernel_size: &[usize],
+    clip_limit: f64,
+    nbins: usize,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    const NB_GREY_LEVELS: usize = 1 << 14;
+
+    assert!(nbins > 0, "`nbins` must be greater than 0");
+    let ndim = data.ndim();
+    let kernel_size = match kernel_size.len() {
+        1 => vec![kernel_size[0]; ndim],
+        l if l == ndim => kernel_size.to_vec(),
+        _ => panic!("Inconsistant number of dimensions and kernel sizes"),
+    };
+    assert!(!kernel_size.contains(&0), "`kernel_size` must be positive");
+
+    let shape = data.shape().to_vec();
+    let (min, max) = data.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+        let v = v.to_f64().unwrap();
+        (min.min(v), max.max(v))
+    });
+    let range = if max > min { max - min } else { 1.0 };
+    // The bin of each voxel, in logical order
+    let bin_size = 1 + NB_GREY_LEVELS / nbins;
+    let bins: Vec<usize> = data
+        .iter()
+        .map(|&v| {
+            let level = (v.to_f64().unwrap() - min) / range * (NB_GREY_LEVELS - 1) as f64;
+            round_ties_even(level) as usize / bin_size
+        })
+        .collect();
+    let mut strides = vec![1; ndim];
+    for d in (0..ndim.saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1];
+    }
+
+    // Histograms of all tiles. The last tile of an axis is completed by reflection.
+    let nb_tiles: Vec<_> = shape.iter().zip(&kernel_size).map(|(&s, &k)| s.div_ceil(k)).collect();
+    let mut tile_strides = vec![1; ndim];
+    for d in (0..ndim.saturating_sub(1)).rev() {
+        tile_strides[d] = tile_strides[d + 1] * nb_tiles[d + 1];
+    }
+    let nb_voxels: usize = kernel_size.iter().product();
+    let limit = if clip_limit > 0.0 {
+        ((clip_limit * nb_voxels as f64) as usize).max(1)
+    } else {
+        nb_voxels
+    };
+    let scale = (NB_GREY_LEVELS - 1) as f64 / nb_voxels as f64;
+    let mut mappings = Vec::with_capacity(nb_tiles.iter().product());
+    for tile in indices(IxDyn(&nb_tiles)) {
+        let mut hist = vec![0; nbins];
+        for offset in indices(IxDyn(&kernel_size)) {
+            let voxel = (0..ndim).fold(0, |acc, d| {
+                let x = tile[d] * kernel_size[d] + offset[d];
+                acc + reflect(x, shape[d]) * strides[d]
+            });
+            hist[bins[voxel]] += 1;
+        }
+        clip_histogram(&mut hist, limit);
+        let mut acc = 0;
+        let mapping: Vec<_> = hist
+            .iter()
+            .map(|&h| {
+                acc += h;
+                (acc as f64 * scale).min((NB_GREY_LEVELS - 1) as f64) as usize
+            })
+            .collect();
+        mappings.push(mapping);
+    }
+
+    // Multilinear interpolation between the mappings of the tiles. Along each axis, the voxel is
+    // between the centers of the tiles `t - 1` and `t`, with a weight `w` for the second one.
+    let mut neighbors = vec![(0, 0, 0.0); ndim];
+    let levels: Vec<_> = indices(IxDyn(&shape))
+        .into_iter()
+        .zip(&bins)
+        .map(|(idx, &bin)| {
+            for d in 0..ndim {
+                let x = idx[d] + kernel_size[d] / 2;
+                let t = x / kernel_size[d];
+                let w = (x % kernel_size[d]) as f64 / kernel_size[d] as f64;
+                neighbors[d] = (t.saturating_sub(1), t.min(nb_tiles[d] - 1), w);
+            }
+
+            // Accumulated in `f32` and truncated to a grey level, like scikit-image
+            let level = (0..1usize << ndim).fold(0.0f32, |acc, corner| {
+                let mut tile = 0;
+                let mut weight = 1.0;
+                for (d, &(t0, t1, w)) in neighbors.iter().enumerate().rev() {
+                    if corner >> (ndim - 1 - d) & 1 == 0 {
+                        tile += t0 * tile_strides[d];
+                        weight *= 1.0 - w;
+                    } else {
+                        tile += t1 * tile_strides[d];
+                        weight *= w;
+                    }
+                }
+                acc + (mappings[tile][bin] as f64 * weight) as f32
+            });
+            level as usize
+        })
+        .collect();
+
+    let (min, max) = levels.iter().fold((usize::MAX, 0), |(min, max), &l| (min.min(l), max.max(l)));
+    let equalized = levels
+        .into_iter()
+        .map(|l| {
+            let v = if max > min {
+                (l - min) as f64 / (max - min) as f64
+            } else {
+                l as f64 / (NB_GREY_LEVELS - 1) as f64
+            };
+            A::from_f64(v).unwrap()
+        })
+        .collect();
+    Array::from_shape_vec(data.raw_dim(), equalized).unwrap()
+}
+
+/// Reflects `x` in `[0, len)`, without repeating the edge values, like NumPy's `reflect` mode.
+fn reflect(x: usize, len: usize) -> usize {
+    if len == 1 {
+        return 0;
+    }
+    let x = x % (2 * (len - 1));
+    if x < len {
+        x
+    } else {
+        2 * (len - 1) - x
+    }
+}
+
+/// Clips the histogram at `limit` and redistributes the clipped counts to the bins under the
+/// limit, like scikit-image. The excess is first spread uniformly, then by steps of one count.
+fn clip_histogram(hist: &mut [usize], limit: usize) {
+    let mut excess = hist.iter_mut().fold(0, |acc, h| {
+        let e = h.saturating_sub(limit);
+        *h -= e;
+        acc + e as isize
+    });
+
+    let increment = excess / hist.len() as isize;
+    let upper = limit as isize - increment;
+    for h in hist.iter_mut().filter(|h| (**h as isize) < upper) {
+        *h += increment as usize;
+        excess -= increment;
+    }
+    for h in hist.iter_mut().filter(|h| (**h as isize) >= upper && **h < limit) {
+        excess -= (limit - *h) as isize;
+        *h = limit;
+    }
+
+    while excess > 0 {
+        let previous_excess = excess;
+        for start in 0..hist.len() {
+            let nb_under = hist.iter().filter(|&&h| h < limit).count() as isize;
+            let step = (nb_under / excess).max(1) as usize;
+            for h in hist[start..].iter_mut().step_by(step).filter(|h| **h < limit) {
+                *h += 1;
+                excess -= 1;
+            }
+            if excess <= 0 {
+                break;
+            }
+        }
+        if excess == previous_excess {
+            break;
+        }
+    }
+}
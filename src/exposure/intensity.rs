@@ -0,0 +1,101 @@
+use ndarray::{Array, ArrayRef, Dimension};
+use num_traits::{FromPrimitive, Num, ToPrimitive};
+
+use crate::{exposure::matching::percentile, threshold::global::values};
+
+/// Input range of [`rescale_intensity`](crate::rescale_intensity).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntensityRange<A> {
+    /// The minimum and maximum of the image.
+    Image,
+    /// The given minimum and maximum values.
+    Values(A, A),
+    /// The given low and high percentiles of the image, in `[0, 100]`. For example, `(0.5, 99.5)`
+    /// ignores the outliers.
+    Percentiles(f64, f64),
+}
+
+/// Linearly rescales the intensities of `data` from `in_range` to `out_range`.
+///
+/// The values outside of `in_range` are clipped. If `A` is an integer type, the rescaled values
+/// are rounded.
+///
+/// * `data` - The input N-D data.
+/// * `in_range` - The input range. See the [`IntensityRange`](crate::IntensityRange) enum for more
+///   information.
+/// * `out_range` - The minimum and maximum of the output.
+/// * `mask` - Optional mask restricting the voxels used to compute `in_range`. The rescaling is
+///   still applied to all voxels.
+///
+/// **Panics** if a percentile is outside of `[0, 100]`, if `mask` doesn't have the same shape as
+/// `data` or if there's no voxel to use.
+pub fn rescale_intensity<A, D>(
+    data: &ArrayRef<A, D>,
+    in_range: IntensityRange<A>,
+    out_range: (A, A),
+    mask: Option<&ArrayRef<bool, D>>,
+) -> Array<A, D>
+where
+    A: Copy + Num + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let (in_low, in_high) = match in_range {
+        IntensityRange::Image => values(data, mask)
+            .into_iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, h), v| (l.min(v), h.max(v))),
+        IntensityRange::Values(low, high) => (low.to_f64().unwrap(), high.to_f64().unwrap()),
+        IntensityRange::Percentiles(low, high) => {
+            assert!(
+                (0.0..=100.0).contains(&low) && (0.0..=100.0).contains(&high),
+                "Percentiles must be in [0, 100]"
+            );
+            let mut values = values(data, mask);
+            values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            (percentile(&values, low), percentile(&values, high))
+        }
+    };
+    let (out_low, out_high) = (out_range.0.to_f64().unwrap(), out_range.1.to_f64().unwrap());
+    let scale = if in_high > in_low { (out_high - out_low) / (in_high - in_low) } else { 0.0 };
+    // The `f64` values are rounded for the integer types
+    let is_integer = crate::is_integer::<A>();
+
+    data.mapv(|v| {
+        let v = v.to_f64().unwrap().clamp(in_low, in_high.max(in_low));
+        let rescaled = out_low + (v - in_low) * scale;
+        let rescaled = if is_integer { rescaled.round() } else { rescaled };
+        // The bounds of the 64-bit integers are rounded outside of their range in `f64`, so the
+        // values that can't be converted are saturated to the nearest end of `out_range`
+        A::from_f64(rescaled).unwrap_or(
+            if (rescaled - out_low).abs() < (rescaled - out_high).abs() {
+                out_range.0
+            } else {
+                out_range.1
+            },
+        )
+    })
+}
+
+/// Normalizes the intensities of `data` to a mean of 0 and a standard deviation of 1.
+///
+/// If all values are equal, only the mean is subtracted.
+///
+/// * `data` - The input N-D data.
+/// * `mask` - Optional mask restricting the voxels used to compute the mean and standard
+///   deviation. The normalization is still applied to all voxels.
+///
+/// **Panics** if `mask` doesn't have the same shape as `data` or if there's no voxel to use.
+pub fn normalize_zscore<A, D>(
+    data: &ArrayRef<A, D>,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> Array<f64, D>
+where
+    A: Copy + Num + ToPrimitive,
+    D: Dimension,
+{
+    let values = values(data, mask);
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let std = if std > 0.0 { std } else { 1.0 };
+    data.mapv(|v| (v.to_f64().unwrap() - mean) / std)
+}
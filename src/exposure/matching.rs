@@ -0,0 +1,130 @@
+use ndarray::{Array, ArrayRef, Dimension};
+use num_traits::{Float, FromPrimitive};
+
+use crate::threshold::global::values;
+
+/// Percentiles used as landmarks by [`HistogramMatchingMethod::Landmarks`].
+const LANDMARKS: [f64; 11] = [1.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 99.0];
+
+/// Method used by [`match_histograms`](crate::match_histograms).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistogramMatchingMethod {
+    /// Exact matching of the cumulative distribution functions. Each value is mapped to the
+    /// reference value with the same quantile.
+    Exact,
+    /// Piecewise-linear matching of landmarks (Nyúl-Udupa). The 1st, 10th, 20th, ..., 90th and 99th
+    /// percentiles of the source are mapped to the same percentiles of the reference, with a linear
+    /// interpolation between them. The mapping is extrapolated linearly beyond the first and last
+    /// landmarks. It's more robust than the exact matching when the images don't have the same
+    /// content.
+    Landmarks,
+}
+
+/// Adjusts the intensities of `source` so that its histogram matches the histogram of
+/// `reference`.
+///
+/// The mapping is computed on the voxels in the masks, then applied to all voxels of `source`.
+///
+/// * `source` - The input N-D data.
+/// * `reference` - The N-D reference image. It doesn't need to have the same shape as `source`.
+/// * `source_mask` - Optional mask restricting the voxels of `source` used to compute the mapping.
+/// * `reference_mask` - Optional mask restricting the voxels of `reference` used to compute the
+///   mapping.
+/// * `method` - See the [`HistogramMatchingMethod`](crate::HistogramMatchingMethod) enum for more
+///   information.
+///
+/// **Panics** if a mask doesn't have the same shape as its image, or if there's no voxel to use.
+pub fn match_histograms<A, D, E>(
+    source: &ArrayRef<A, D>,
+    reference: &ArrayRef<A, E>,
+    source_mask: Option<&ArrayRef<bool, D>>,
+    reference_mask: Option<&ArrayRef<bool, E>>,
+    method: HistogramMatchingMethod,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+    E: Dimension,
+{
+    let mut source_values = values(source, source_mask);
+    let mut reference_values = values(reference, reference_mask);
+    source_values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    reference_values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+    match method {
+        HistogramMatchingMethod::Exact => {
+            let (source_values, source_quantiles) = quantiles(&source_values);
+            let (reference_values, reference_quantiles) = quantiles(&reference_values);
+            source.mapv(|v| {
+                let q = interpolate(v.to_f64().unwrap(), &source_values, &source_quantiles, false);
+                A::from_f64(interpolate(q, &reference_quantiles, &reference_values, false)).unwrap()
+            })
+        }
+        HistogramMatchingMethod::Landmarks => {
+            let mut source_landmarks = LANDMARKS.map(|p| percentile(&source_values, p)).to_vec();
+            let reference_landmarks = LANDMARKS.map(|p| percentile(&reference_values, p));
+            // The landmarks must be strictly increasing to be interpolated. The step is relative,
+            // so it can't be lost in the rounding of large values.
+            for i in 1..source_landmarks.len() {
+                let previous = source_landmarks[i - 1];
+                if source_landmarks[i] <= previous {
+                    source_landmarks[i] = previous + previous.abs().max(1.0) * f64::EPSILON;
+                }
+            }
+            source.mapv(|v| {
+                let v = v.to_f64().unwrap();
+                A::from_f64(interpolate(v, &source_landmarks, &reference_landmarks, true)).unwrap()
+            })
+        }
+    }
+}
+
+/// Returns the unique values of the sorted `values` and their cumulative quantiles.
+fn quantiles(values: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let n = values.len() as f64;
+    let mut unique: Vec<f64> = vec![];
+    let mut quantiles: Vec<f64> = vec![];
+    for (i, &v) in values.iter().enumerate() {
+        if unique.last() == Some(&v) {
+            *quantiles.last_mut().unwrap() = (i + 1) as f64 / n;
+        } else {
+            unique.push(v);
+            quantiles.push((i + 1) as f64 / n);
+        }
+    }
+    (unique, quantiles)
+}
+
+/// Returns the percentile `p` of the sorted `values`, with a linear interpolation between the
+/// closest ranks.
+pub(crate) fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (values.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    values[low] + (rank - low as f64) * (values[high] - values[low])
+}
+
+/// Piecewise-linear interpolation of the points `(xs, ys)` at `x`. `xs` must be increasing.
+///
+/// Outside of `xs`, the first and last `ys` are returned, or the first and last segments are
+/// extended if `extrapolate` is `true`.
+fn interpolate(x: f64, xs: &[f64], ys: &[f64], extrapolate: bool) -> f64 {
+    if xs.len() == 1 {
+        return ys[0];
+    }
+    let i = xs.partition_point(|&v| v <= x);
+    let segment = if i == 0 {
+        if !extrapolate {
+            return ys[0];
+        }
+        1
+    } else if i == xs.len() {
+        if !extrapolate {
+            return ys[i - 1];
+        }
+        i - 1
+    } else {
+        i
+    };
+    let t = (x - xs[segment - 1]) / (xs[segment] - xs[segment - 1]);
+    ys[segment - 1] + t * (ys[segment] - ys[segment - 1])
+}
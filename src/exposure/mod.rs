@@ -0,0 +1,3 @@
+pub mod equalization;
+pub mod intensity;
+pub mod matching;
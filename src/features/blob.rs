@@ -0,0 +1,298 @@
+use std::f64::consts::PI;
+
+use ndarray::{indices, Array, ArrayRef, Dimension, IxDyn};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{
+    filters::{gaussian::gaussian_filter_axes, symmetry::SymmetryStateCheck},
+    gaussian_filter, BorderMode,
+};
+
+/// A blob detected by [`blob_log`](crate::blob_log) or [`blob_dog`](crate::blob_dog).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blob<A> {
+    /// Sub-voxel coordinates of the center of the blob.
+    pub center: Vec<A>,
+    /// Standard deviation of the Gaussian kernel that detected the blob. The radius of the blob is
+    /// approximately `sigma * sqrt(ndim)`.
+    pub sigma: A,
+}
+
+/// Finds blobs using the Laplacian of Gaussian (LoG) method.
+///
+/// The scale-normalized LoG is computed for `num_sigma` standard deviations, linearly spaced
+/// between `min_sigma` and `max_sigma`. The blobs are the local maxima of this scale-space,
+/// refined to sub-voxel (and sub-scale) precision by fitting a parabola along each axis. The image
+/// is extended beyond its boundaries with the [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// Bright blobs on a dark background are detected.
+///
+/// * `data` - The input N-D data.
+/// * `min_sigma` - The minimum standard deviation of the Gaussian kernel. Keep it low to detect
+///   smaller blobs.
+/// * `max_sigma` - The maximum standard deviation of the Gaussian kernel. Keep it high to detect
+///   larger blobs.
+/// * `num_sigma` - The number of intermediate values of standard deviations to consider.
+/// * `threshold` - The absolute lower bound for scale-space maxima. Reduce it to detect blobs with
+///   lower intensities.
+/// * `overlap` - A value between 0 and 1. If the overlapping fraction of two blobs is greater than
+///   `overlap`, the smaller blob is eliminated.
+///
+/// **Panics** if `min_sigma` is not positive, if `num_sigma` is 0 or if one of the axis' lengths
+/// is lower than `4 * max_sigma + 0.5`.
+pub fn blob_log<A, D>(
+    data: &ArrayRef<A, D>,
+    min_sigma: A,
+    max_sigma: A,
+    num_sigma: usize,
+    threshold: A,
+    overlap: A,
+) -> Vec<Blob<A>>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    assert!(min_sigma > A::zero(), "`min_sigma` must be positive");
+    assert!(num_sigma > 0, "`num_sigma` must be greater than 0");
+    let sigmas: Vec<_> = if num_sigma == 1 {
+        vec![min_sigma]
+    } else {
+        let step = (max_sigma - min_sigma) / A::from_usize(num_sigma - 1).unwrap();
+        (0..num_sigma).map(|i| min_sigma + step * A::from_usize(i).unwrap()).collect()
+    };
+
+    let n = data.ndim();
+    let cube: Vec<_> = sigmas
+        .iter()
+        .map(|&sigma| {
+            // Scale-normalized and negated Laplacian of Gaussian
+            let mut log = Array::zeros(data.raw_dim());
+            for d in 0..n {
+                let mut orders = vec![0; n];
+                orders[d] = 2;
+                let derivative =
+                    gaussian_filter_axes(data, &vec![sigma; n], &orders, BorderMode::Reflect, 4);
+                log.zip_mut_with(&derivative, |l, &v| *l = *l - v * sigma * sigma);
+            }
+            log
+        })
+        .collect();
+    detect_blobs(&cube, &sigmas, threshold, overlap)
+}
+
+/// Finds blobs using the Difference of Gaussians (DoG) method.
+///
+/// The image is blurred with Gaussian kernels of standard deviations `min_sigma * sigma_ratio^i`,
+/// up to `max_sigma`, and the scale-normalized differences between successive blurred images are
+/// computed. The blobs are the local maxima of this scale-space, refined to sub-voxel (and
+/// sub-scale) precision by fitting a parabola along each axis. The image is extended beyond its
+/// boundaries with the [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// Bright blobs on a dark background are detected.
+///
+/// * `data` - The input N-D data.
+/// * `min_sigma` - The minimum standard deviation of the Gaussian kernel. Keep it low to detect
+///   smaller blobs.
+/// * `max_sigma` - The maximum standard deviation of the Gaussian kernel. Keep it high to detect
+///   larger blobs.
+/// * `sigma_ratio` - The ratio between the standard deviations of two successive Gaussian kernels.
+/// * `threshold` - The absolute lower bound for scale-space maxima. Reduce it to detect blobs with
+///   lower intensities.
+/// * `overlap` - A value between 0 and 1. If the overlapping fraction of two blobs is greater than
+///   `overlap`, the smaller blob is eliminated.
+///
+/// **Panics** if `min_sigma` is not positive, if `sigma_ratio <= 1`, if `max_sigma < min_sigma` or
+/// if one of the axis' lengths is lower than `4 * max_sigma * sigma_ratio + 0.5`.
+pub fn blob_dog<A, D>(
+    data: &ArrayRef<A, D>,
+    min_sigma: A,
+    max_sigma: A,
+    sigma_ratio: A,
+    threshold: A,
+    overlap: A,
+) -> Vec<Blob<A>>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    assert!(min_sigma > A::zero(), "`min_sigma` must be positive");
+    assert!(sigma_ratio > A::one(), "`sigma_ratio` must be greater than 1");
+    assert!(max_sigma >= min_sigma, "`max_sigma` must be greater than `min_sigma`");
+
+    // `k` such that `min_sigma * sigma_ratio^k > max_sigma`
+    let k = ((max_sigma / min_sigma).ln() / sigma_ratio.ln()).to_usize().unwrap() + 1;
+    let sigmas: Vec<_> = (0..=k).map(|i| min_sigma * sigma_ratio.powi(i as i32)).collect();
+    let blurred: Vec<_> =
+        sigmas.iter().map(|&s| gaussian_filter(data, s, 0, BorderMode::Reflect, 4)).collect();
+    // `G(s) - G(k * s)` approximates `(k - 1) * s² * LoG`, so it's already scale-normalized
+    let factor = (sigma_ratio - A::one()).recip();
+    let cube: Vec<_> = blurred
+        .windows(2)
+        .map(|w| {
+            let mut dog = w[0].clone();
+            dog.zip_mut_with(&w[1], |d, &v| *d = (*d - v) * factor);
+            dog
+        })
+        .collect();
+    detect_blobs(&cube, &sigmas[..k], threshold, overlap)
+}
+
+/// Finds the local maxima of the scale-space `cube` and prunes the overlapping blobs.
+fn detect_blobs<A, D>(cube: &[Array<A, D>], sigmas: &[A], threshold: A, overlap: A) -> Vec<Blob<A>>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    let shape = cube[0].shape().to_vec();
+    let ndim = shape.len();
+    let mut strides = vec![1; ndim];
+    for d in (0..ndim - 1).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1] as isize;
+    }
+    // All voxels are visited in logical order, so we can use the strides of a standard layout
+    let cube: Vec<Vec<A>> = cube.iter().map(|c| c.iter().cloned().collect()).collect();
+    let nb_scales = cube.len();
+
+    // All the offsets of a 3x3x...x3 neighborhood in the scale-space
+    let mut neighborhood = vec![vec![]];
+    for _ in 0..=ndim {
+        neighborhood = neighborhood
+            .into_iter()
+            .flat_map(|v: Vec<isize>| {
+                (-1..=1).map(move |o| {
+                    let mut v = v.clone();
+                    v.push(o);
+                    v
+                })
+            })
+            .collect();
+    }
+
+    let half = A::from(0.5).unwrap();
+    let mut blobs = vec![];
+    for (i, idx) in indices(IxDyn(&shape)).into_iter().enumerate() {
+        for s in 0..nb_scales {
+            let v = cube[s][i];
+            if v <= threshold {
+                continue;
+            }
+
+            let is_maximum = neighborhood.iter().all(|delta| {
+                let scale = s as isize + delta[ndim];
+                if scale < 0 || scale >= nb_scales as isize {
+                    return true;
+                }
+                let mut j = i as isize;
+                for d in 0..ndim {
+                    let c = idx[d] as isize + delta[d];
+                    if c < 0 || c >= shape[d] as isize {
+                        return true;
+                    }
+                    j += delta[d] * strides[d];
+                }
+                cube[scale as usize][j as usize] <= v
+            });
+            if !is_maximum {
+                continue;
+            }
+
+            // Fit a parabola on the 3 values around the maximum, along an axis
+            let refine = |before: A, after: A| {
+                let denominator = before - (v + v) + after;
+                if denominator < A::zero() {
+                    (half * (before - after) / denominator).max(-half).min(half)
+                } else {
+                    A::zero()
+                }
+            };
+
+            let center = (0..ndim)
+                .map(|d| {
+                    let c = A::from_usize(idx[d]).unwrap();
+                    if idx[d] == 0 || idx[d] == shape[d] - 1 {
+                        c
+                    } else {
+                        let stride = strides[d] as usize;
+                        c + refine(cube[s][i - stride], cube[s][i + stride])
+                    }
+                })
+                .collect();
+            let sigma = if s == 0 || s == nb_scales - 1 {
+                sigmas[s]
+            } else {
+                let offset = refine(cube[s - 1][i], cube[s + 1][i]);
+                if offset < A::zero() {
+                    sigmas[s] + offset * (sigmas[s] - sigmas[s - 1])
+                } else {
+                    sigmas[s] + offset * (sigmas[s + 1] - sigmas[s])
+                }
+            };
+            blobs.push(Blob { center, sigma });
+        }
+    }
+
+    prune_blobs(blobs, overlap)
+}
+
+/// Eliminates the smaller blob of each pair of blobs overlapping more than `overlap`.
+fn prune_blobs<A>(mut blobs: Vec<Blob<A>>, overlap: A) -> Vec<Blob<A>>
+where
+    A: Float + FromPrimitive,
+{
+    let overlap = overlap.to_f64().unwrap();
+    for i in 0..blobs.len() {
+        for j in i + 1..blobs.len() {
+            let (b1, b2) = (&blobs[i], &blobs[j]);
+            if b1.sigma > A::zero() && b2.sigma > A::zero() && blob_overlap(b1, b2) > overlap {
+                if b1.sigma > b2.sigma {
+                    blobs[j].sigma = A::zero();
+                } else {
+                    blobs[i].sigma = A::zero();
+                }
+            }
+        }
+    }
+    blobs.retain(|b| b.sigma > A::zero());
+    blobs
+}
+
+/// Returns the overlapping fraction of the smaller blob.
+///
+/// The blobs are considered as disks in 2D and as spheres in all other dimensions.
+fn blob_overlap<A: Float>(b1: &Blob<A>, b2: &Blob<A>) -> f64 {
+    let (s1, s2) = (b1.sigma.to_f64().unwrap(), b2.sigma.to_f64().unwrap());
+    let ndim = b1.center.len();
+
+    // Rescale the space so that the larger blob has a radius of 1
+    let max_sigma = s1.max(s2);
+    let (r1, r2) = (s1 / max_sigma, s2 / max_sigma);
+    let scale = max_sigma * (ndim as f64).sqrt();
+    let d = b1
+        .center
+        .iter()
+        .zip(&b2.center)
+        .fold(0.0, |acc, (&c1, &c2)| acc + ((c1 - c2).to_f64().unwrap() / scale).powi(2))
+        .sqrt();
+
+    if d > r1 + r2 {
+        0.0
+    } else if d <= (r1 - r2).abs() {
+        1.0
+    } else if ndim == 2 {
+        let acos1 = ((d * d + r1 * r1 - r2 * r2) / (2.0 * d * r1)).clamp(-1.0, 1.0).acos();
+        let acos2 = ((d * d + r2 * r2 - r1 * r1) / (2.0 * d * r2)).clamp(-1.0, 1.0).acos();
+        let a = -d + r2 + r1;
+        let b = d - r2 + r1;
+        let c = d + r2 - r1;
+        let e = d + r2 + r1;
+        let area = r1 * r1 * acos1 + r2 * r2 * acos2 - 0.5 * (a * b * c * e).abs().sqrt();
+        area / (PI * r1.min(r2).powi(2))
+    } else {
+        let volume = PI / (12.0 * d)
+            * (r1 + r2 - d).powi(2)
+            * (d * d + 2.0 * d * (r1 + r2) - 3.0 * (r1 * r1 + r2 * r2) + 6.0 * r1 * r2);
+        volume / (4.0 / 3.0 * PI * r1.min(r2).powi(3))
+    }
+}
@@ -0,0 +1,115 @@
+use ndarray::{indices, Array, ArrayRef, Axis, Dimension, IxDyn, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Signed};
+
+use crate::{filters::symmetry::SymmetryStateCheck, gaussian_filter, sobel, BorderMode};
+
+/// Canny edge detector for 2D and 3D images.
+///
+/// The image is smoothed with a Gaussian filter, then the gradient is computed with the Sobel
+/// operator. The voxels that are not a local maximum of the gradient magnitude along the gradient
+/// direction are removed (non-maximum suppression). Finally, the edges are selected by hysteresis
+/// thresholding: all voxels with a magnitude greater than `high_threshold` are edges, and so are
+/// the voxels with a magnitude greater than `low_threshold` connected to them.
+///
+/// The voxels on the border of the image, or of the mask, are never edges.
+///
+/// * `data` - The input 2D or 3D data.
+/// * `sigma` - Standard deviation of the Gaussian filter.
+/// * `low_threshold` - Lower bound for hysteresis thresholding, on the Sobel magnitude.
+/// * `high_threshold` - Upper bound for hysteresis thresholding, on the Sobel magnitude.
+/// * `mask` - Optional mask restricting the edge detection to the `true` voxels.
+///
+/// **Panics** if `data` is not 2D or 3D, if `low_threshold > high_threshold` or if `mask` doesn't
+/// have the same shape as `data`.
+pub fn canny<A, D>(
+    data: &ArrayRef<A, D>,
+    sigma: A,
+    low_threshold: A,
+    high_threshold: A,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> Array<bool, D>
+where
+    A: Float + FromPrimitive + Signed + ScalarOperand + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let ndim = data.ndim();
+    assert!(ndim == 2 || ndim == 3, "Canny edge detector is only defined on 2D or 3D data");
+    assert!(low_threshold <= high_threshold, "`low_threshold` must be <= `high_threshold`");
+    if let Some(mask) = mask {
+        assert_eq!(mask.shape(), data.shape(), "`mask` must have the same shape as `data`");
+    }
+
+    let smoothed = gaussian_filter(data, sigma, 0, BorderMode::Nearest, 4);
+    let gradients: Vec<Vec<A>> = (0..ndim)
+        .map(|d| sobel(&smoothed, Axis(d), BorderMode::Nearest).iter().cloned().collect())
+        .collect();
+    let magnitude: Vec<A> = (0..data.len())
+        .map(|i| gradients.iter().fold(A::zero(), |acc, g| acc + g[i] * g[i]).sqrt())
+        .collect();
+
+    // All voxels are visited in logical order, so we can use the strides of a standard layout
+    let shape = data.shape().to_vec();
+    let mut strides = vec![1; ndim];
+    for d in (0..ndim - 1).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1] as isize;
+    }
+    let neighbors = neighbor_offsets(&strides);
+    let mask: Option<Vec<bool>> = mask.map(|mask| mask.iter().cloned().collect());
+
+    let mut weak = vec![false; data.len()];
+    let mut edges = vec![false; data.len()];
+    let mut strong = vec![];
+    for (i, idx) in indices(IxDyn(&shape)).into_iter().enumerate() {
+        let m = magnitude[i];
+        if m < low_threshold || m == A::zero() {
+            continue;
+        }
+        if (0..ndim).any(|d| idx[d] == 0 || idx[d] == shape[d] - 1) {
+            continue;
+        }
+        if let Some(mask) = &mask {
+            if !mask[i] || neighbors.iter().any(|&o| !mask[(i as isize + o) as usize]) {
+                continue;
+            }
+        }
+
+        // Non-maximum suppression, using the neighbor closest to the gradient direction
+        let max = gradients.iter().fold(A::zero(), |acc, g| Float::max(acc, Float::abs(g[i])));
+        let offset = gradients
+            .iter()
+            .zip(&strides)
+            .fold(0, |acc, (g, &s)| acc + (g[i] / max).round().to_isize().unwrap() * s);
+        let (next, previous) = ((i as isize + offset) as usize, (i as isize - offset) as usize);
+        if m >= magnitude[next] && m > magnitude[previous] {
+            weak[i] = true;
+            if m >= high_threshold {
+                edges[i] = true;
+                strong.push(i);
+            }
+        }
+    }
+
+    // Hysteresis. The weak voxels connected to a strong voxel are edges.
+    while let Some(i) = strong.pop() {
+        for &o in &neighbors {
+            let j = (i as isize + o) as usize;
+            if weak[j] && !edges[j] {
+                edges[j] = true;
+                strong.push(j);
+            }
+        }
+    }
+
+    Array::from_shape_vec(data.raw_dim(), edges).unwrap()
+}
+
+/// Returns the offsets of all neighbors (full connectivity) in an array with the given `strides`.
+fn neighbor_offsets(strides: &[isize]) -> Vec<isize> {
+    let mut offsets = vec![0];
+    for &s in strides {
+        offsets = offsets.iter().flat_map(|&o| [o - s, o, o + s]).collect();
+    }
+    offsets.retain(|&o| o != 0);
+    offsets
+}
@@ -0,0 +1,138 @@
+use ndarray::{Array, Dimension};
+use num_traits::Float;
+
+/// Maximal number of sweeps of the Jacobi method. It usually converges in less than 10 sweeps.
+const MAX_SWEEPS: usize = 50;
+
+/// Eigen decomposition of a symmetric matrix, using the cyclic Jacobi method.
+///
+/// * `matrix` - Row-major `n x n` symmetric matrix. It's modified by this function.
+/// * `values` - Output eigenvalues, sorted in decreasing order.
+/// * `vectors` - Output row-major `n x n` matrix. The columns are the normalized eigenvectors,
+///   in the same order as `values`.
+pub fn symmetric_eigen<A: Float>(matrix: &mut [A], n: usize, values: &mut [A], vectors: &mut [A]) {
+    vectors.iter_mut().for_each(|v| *v = A::zero());
+    for i in 0..n {
+        vectors[i * n + i] = A::one();
+    }
+
+    // The rotations don't change the Frobenius norm of the matrix, so it can be used as a scale
+    let tolerance = matrix.iter().fold(A::zero(), |acc, &v| acc + v * v) * A::epsilon().powi(2);
+    for _ in 0..MAX_SWEEPS {
+        let mut converged = true;
+        for p in 0..n {
+            for q in p + 1..n {
+                let apq = matrix[p * n + q];
+                if apq * apq <= tolerance {
+                    continue;
+                }
+                converged = false;
+
+                let two = A::one() + A::one();
+                let theta = (matrix[q * n + q] - matrix[p * n + p]) / (two * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + A::one()).sqrt());
+                let c = (t * t + A::one()).sqrt().recip();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (matrix[k * n + p], matrix[k * n + q]);
+                    matrix[k * n + p] = c * akp - s * akq;
+                    matrix[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (matrix[p * n + k], matrix[q * n + k]);
+                    matrix[p * n + k] = c * apk - s * aqk;
+                    matrix[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (vectors[k * n + p], vectors[k * n + q]);
+                    vectors[k * n + p] = c * vkp - s * vkq;
+                    vectors[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+        if converged {
+            break;
+        }
+    }
+
+    // Selection sort, in decreasing order. `n` is tiny so there's no need for anything smarter.
+    for i in 0..n {
+        values[i] = matrix[i * n + i];
+    }
+    for i in 0..n {
+        let max = (i..n).fold(i, |max, j| if values[j] > values[max] { j } else { max });
+        if max != i {
+            values.swap(i, max);
+            for k in 0..n {
+                vectors.swap(k * n + i, k * n + max);
+            }
+        }
+    }
+}
+
+/// Calls `f` on the symmetric matrix of each voxel, in logical order.
+///
+/// `components` are the `n * (n + 1) / 2` images of the upper triangle of the matrices, in
+/// row-major order. The matrix given to `f` is a full row-major `n x n` matrix.
+pub fn for_each_symmetric_matrix<A, D, F>(components: &[Array<A, D>], mut f: F)
+where
+    A: Float,
+    D: Dimension,
+    F: FnMut(&mut [A]),
+{
+    let n = components[0].ndim();
+    assert_eq!(components.len(), n * (n + 1) / 2, "Wrong number of components");
+
+    let mut iters: Vec<_> = components.iter().map(|c| c.iter()).collect();
+    let mut matrix = vec![A::zero(); n * n];
+    for _ in 0..components[0].len() {
+        let mut iter = iters.iter_mut();
+        for i in 0..n {
+            for j in i..n {
+                let v = *iter.next().unwrap().next().unwrap();
+                matrix[i * n + j] = v;
+                matrix[j * n + i] = v;
+            }
+        }
+        f(&mut matrix);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let mut values = [0.0; 3];
+        let mut vectors = [0.0; 9];
+        let mut matrix = [2.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 5.0];
+        symmetric_eigen(&mut matrix, 3, &mut values, &mut vectors);
+        assert_eq!(values, [5.0, 2.0, -1.0]);
+        assert_eq!(vectors, [0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+
+        let original = [4.0, 1.0, 2.0, 1.0, 3.0, 0.5, 2.0, 0.5, 1.0];
+        let mut matrix = original;
+        symmetric_eigen(&mut matrix, 3, &mut values, &mut vectors);
+        assert_relative_eq!(&values[..], &[5.5, 2.5, 0.0][..], epsilon = 1e-12);
+        // A * v = lambda * v
+        for (c, &lambda) in values.iter().enumerate() {
+            for r in 0..3 {
+                let av = (0..3).fold(0.0, |acc, k| acc + original[r * 3 + k] * vectors[k * 3 + c]);
+                assert_relative_eq!(av, lambda * vectors[r * 3 + c], epsilon = 1e-10);
+            }
+        }
+
+        let mut values = [0.0; 2];
+        let mut vectors = [0.0; 4];
+        let mut matrix = [1.0, 2.0, 2.0, 1.0];
+        symmetric_eigen(&mut matrix, 2, &mut values, &mut vectors);
+        assert_relative_eq!(&values[..], &[3.0, -1.0][..], epsilon = 1e-12);
+        let h = 0.5f64.sqrt();
+        assert_relative_eq!(vectors[0].abs(), h, epsilon = 1e-12);
+        assert_relative_eq!(vectors[0], vectors[2], epsilon = 1e-12);
+        assert_relative_eq!(vectors[1], -vectors[3], epsilon = 1e-12);
+    }
+}
@@ -31,9 +31,25 @@ where
     for<'a> &'a [A]: SymmetryStateCheck,
     D: Dimension,
 {
-    let weights = weights(sigma, order, truncate);
-    let half = weights.len() / 2;
+    let n = data.ndim();
+    gaussian_filter_axes(data, &vec![sigma; n], &vec![order; n], mode, truncate)
+}
 
+/// Gaussian filter for n-dimensional arrays, with a standard deviation and an order per axis.
+///
+/// See [`gaussian_filter`](crate::gaussian_filter).
+pub(crate) fn gaussian_filter_axes<A, D>(
+    data: &ArrayRef<A, D>,
+    sigmas: &[A],
+    orders: &[usize],
+    mode: BorderMode<A>,
+    truncate: usize,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
     // We need 2 buffers because
     // * We're reading neighbours so we can't read and write on the same location.
     // * The process is applied for each axis on the result of the previous process.
@@ -42,6 +58,9 @@ where
     let mut output = array_like(&data, data.dim(), A::zero());
 
     for d in 0..data.ndim() {
+        let weights = weights(sigmas[d], orders[d], truncate);
+        let half = weights.len() / 2;
+
         // TODO This can be made to work if the padding modes (`reflect`, `symmetric`, `wrap`) are
         // more robust. One just needs to reflect the input data several times if the `weights`
         // length is greater than the input array. It works in SciPy because they are looping on a
@@ -91,6 +110,35 @@ where
     output
 }
 
+/// Difference of Gaussians filter for n-dimensional arrays.
+///
+/// Subtracts a blurred version of the image from a less blurred version of the image. This is a
+/// band-pass filter, which enhances the features whose size is between the two scales. The image
+/// is extended beyond its boundaries with the [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// * `data` - The input N-D data.
+/// * `sigma_low` - Standard deviation of the Gaussian kernel with the smaller standard deviation.
+/// * `sigma_high` - Standard deviation of the Gaussian kernel with the larger standard deviation.
+///
+/// **Panics** if `sigma_low >= sigma_high` or if one of the axis' lengths is lower than
+/// `4 * sigma_high + 0.5`.
+pub fn difference_of_gaussians<A, D>(
+    data: &ArrayRef<A, D>,
+    sigma_low: A,
+    sigma_high: A,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    assert!(sigma_low < sigma_high, "`sigma_low` must be lower than `sigma_high`");
+    let mut low = gaussian_filter(data, sigma_low, 0, BorderMode::Reflect, 4);
+    let high = gaussian_filter(data, sigma_high, 0, BorderMode::Reflect, 4);
+    Zip::from(&mut low).and(&high).for_each(|l, &h| *l = *l - h);
+    low
+}
+
 /// Computes a 1-D Gaussian convolution kernel.
 fn weights<A>(sigma: A, order: usize, truncate: usize) -> Vec<A>
 where
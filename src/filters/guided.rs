@@ -0,0 +1,55 @@
+use ndarray::{Array, ArrayRef, Dimension, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{uniform_filter, BorderMode};
+
+/// Edge-preserving smoothing of `src`, following the edges of `guide`.
+///
+/// In each window of length `2 * radius + 1`, the output is modeled as a linear transform
+/// `a * guide + b` fitted to `src` by ridge regression. The coefficients of all windows covering a
+/// voxel are then averaged. All statistics are box means computed with
+/// [`uniform_filter`](crate::uniform_filter), so the cost doesn't depend on `radius`. The image is
+/// extended beyond its boundaries with the [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// Use the same image as `guide` and `src` for an edge-preserving smoothing of a single image.
+///
+/// * `guide` - The N-D guide image, usually a structural image.
+/// * `src` - The N-D image to filter, for example a parameter map.
+/// * `radius` - Radius of the windows.
+/// * `eps` - Regularization. The edges of `guide` with a variance much lower than `eps` are
+///   smoothed.
+///
+/// **Panics** if `guide` and `src` don't have the same shape, or if one of the axis' lengths is
+/// lower than `radius`.
+pub fn guided_filter<A, D>(
+    guide: &ArrayRef<A, D>,
+    src: &ArrayRef<A, D>,
+    radius: usize,
+    eps: A,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    D: Dimension,
+{
+    assert_eq!(guide.shape(), src.shape(), "`guide` and `src` must have the same shape");
+    let size = 2 * radius + 1;
+    let mean = |data: &Array<A, D>| uniform_filter(data, size, BorderMode::Reflect);
+
+    let mean_guide = uniform_filter(guide, size, BorderMode::Reflect);
+    let mean_src = uniform_filter(src, size, BorderMode::Reflect);
+    let corr_guide = mean(&guide.mapv(|v| v * v));
+    let corr_guide_src = mean(&Zip::from(guide).and(src).map_collect(|&g, &s| g * s));
+
+    // The buffers are reused to compute the coefficients `a` and `b` of all windows
+    let (mut a, mut b) = (corr_guide, corr_guide_src);
+    Zip::from(&mut a).and(&mut b).and(&mean_guide).and(&mean_src).for_each(|a, b, &mg, &ms| {
+        let variance = *a - mg * mg;
+        let covariance = *b - mg * ms;
+        *a = covariance / (variance + eps);
+        *b = ms - *a * mg;
+    });
+
+    let mut out = mean(&a);
+    Zip::from(&mut out).and(&mean(&b)).and(guide).for_each(|o, &b, &g| *o = *o * g + b);
+    out
+}
@@ -0,0 +1,85 @@
+use ndarray::{Array, ArrayRef, Dimension};
+use num_traits::{Float, FromPrimitive};
+
+use super::{
+    eigen::{for_each_symmetric_matrix, symmetric_eigen},
+    gaussian::gaussian_filter_axes,
+    symmetry::SymmetryStateCheck,
+};
+use crate::{read_spacing, BorderMode};
+
+/// Computes the Hessian matrix of a N-D image.
+///
+/// The second derivatives are computed by convolving with the second derivatives of a Gaussian
+/// kernel. The image is extended beyond its boundaries with the
+/// [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// Returns the `n * (n + 1) / 2` components of the upper triangle of the matrix, in row-major
+/// order. For example, for a 3D image, `[Hxx, Hxy, Hxz, Hyy, Hyz, Hzz]`.
+///
+/// * `data` - The input N-D data.
+/// * `sigma` - Standard deviation of the Gaussian kernel, in the same unit as `spacing`.
+/// * `spacing` - The voxel size along each axis. A single value can be given for all axes.
+///
+/// **Panics** if `spacing` doesn't have 1 or `data.ndim()` values, or if one of the axis' lengths
+/// is lower than `4 * sigma + 0.5` voxels.
+pub fn hessian_matrix<A, D>(data: &ArrayRef<A, D>, sigma: A, spacing: &[A]) -> Vec<Array<A, D>>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let n = data.ndim();
+    let spacing = read_spacing(n, spacing);
+    let sigmas: Vec<_> = spacing.iter().map(|&h| sigma / h).collect();
+
+    let mut components = Vec::with_capacity(n * (n + 1) / 2);
+    for i in 0..n {
+        for j in i..n {
+            let mut orders = vec![0; n];
+            orders[i] += 1;
+            orders[j] += 1;
+            let mut component =
+                gaussian_filter_axes(data, &sigmas, &orders, BorderMode::Reflect, 4);
+
+            // The derivatives are computed in voxel units. Convert them to physical units.
+            let scale = (spacing[i] * spacing[j]).recip();
+            if scale != A::one() {
+                component.mapv_inplace(|v| v * scale);
+            }
+            components.push(component);
+        }
+    }
+    components
+}
+
+/// Computes the eigenvalues of the Hessian matrix of each voxel.
+///
+/// Returns `n` images, where the first one holds the greatest eigenvalue of each voxel and the
+/// last one holds the lowest eigenvalue.
+///
+/// * `hessian` - The components of the Hessian matrix, as returned by
+///   [`hessian_matrix`](crate::hessian_matrix).
+///
+/// **Panics** if the number of components is not `n * (n + 1) / 2`.
+pub fn hessian_eigenvalues<A, D>(hessian: &[Array<A, D>]) -> Vec<Array<A, D>>
+where
+    A: Float,
+    D: Dimension,
+{
+    let n = hessian[0].ndim();
+    let len = hessian[0].len();
+    let mut eigenvalues = vec![Vec::with_capacity(len); n];
+    let mut values = vec![A::zero(); n];
+    let mut vectors = vec![A::zero(); n * n];
+    for_each_symmetric_matrix(hessian, |matrix| {
+        symmetric_eigen(matrix, n, &mut values, &mut vectors);
+        for (e, &v) in eigenvalues.iter_mut().zip(&values) {
+            e.push(v);
+        }
+    });
+    eigenvalues
+        .into_iter()
+        .map(|e| Array::from_shape_vec(hessian[0].raw_dim(), e).unwrap())
+        .collect()
+}
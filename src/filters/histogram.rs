@@ -0,0 +1,283 @@
+use ndarray::{Array, ArrayRef, Dimension, IntoDimension};
+use num_traits::ToPrimitive;
+
+/// Integer types that can be used in a [`Histogram`], with one bin per possible value.
+pub trait HistogramType: Copy + ToPrimitive {
+    /// Number of bins, `max_value + 1`.
+    const NB_BINS: usize;
+}
+
+impl HistogramType for u8 {
+    const NB_BINS: usize = 1 << 8;
+}
+
+impl HistogramType for u16 {
+    const NB_BINS: usize = 1 << 16;
+}
+
+/// Histogram of the voxels in a window, with one bin per possible value.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    bins: Vec<usize>,
+    total: usize,
+    /// `Σ c * log2(c)` over all bins, updated incrementally to compute the entropy in `O(1)`.
+    c_log_c: f64,
+}
+
+impl Histogram {
+    fn new(nb_bins: usize) -> Histogram {
+        Histogram { bins: vec![0; nb_bins], total: 0, c_log_c: 0.0 }
+    }
+
+    fn add(&mut self, bin: usize) {
+        let c = self.bins[bin];
+        self.c_log_c += x_log2_x(c + 1) - x_log2_x(c);
+        self.bins[bin] = c + 1;
+        self.total += 1;
+    }
+
+    fn remove(&mut self, bin: usize) {
+        let c = self.bins[bin];
+        self.c_log_c += x_log2_x(c - 1) - x_log2_x(c);
+        self.bins[bin] = c - 1;
+        self.total -= 1;
+        if self.total == 0 {
+            // Reset the accumulated rounding errors
+            self.c_log_c = 0.0;
+        }
+    }
+
+    /// The number of voxels of each value.
+    pub fn bins(&self) -> &[usize] {
+        &self.bins
+    }
+
+    /// The number of voxels in the window. It's lower than the number of `true` values of the
+    /// footprint on the borders of the image.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The Shannon entropy of the values in the window, in bits.
+    pub fn entropy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let total = self.total as f64;
+        (total.log2() - self.c_log_c / total).max(0.0)
+    }
+
+    /// The value of rank `rank` in the window, where 0 is the minimum and `total() - 1` is the
+    /// maximum. Returns `None` if `rank >= total()`.
+    pub fn rank(&self, rank: usize) -> Option<usize> {
+        let mut count = 0;
+        self.bins.iter().position(|&c| {
+            count += c;
+            count > rank
+        })
+    }
+}
+
+fn x_log2_x(x: usize) -> f64 {
+    if x == 0 {
+        0.0
+    } else {
+        x as f64 * (x as f64).log2()
+    }
+}
+
+/// Histograms of the windows centered on all voxels, in logical order.
+///
+/// Returned by [`local_histogram`](crate::local_histogram). The histogram is updated incrementally
+/// as the window slides along the last axis (Huang's algorithm): only the voxels entering and
+/// leaving the window are counted, instead of the whole window.
+///
+/// This isn't an [`Iterator`] because all histograms share the same memory.
+///
+/// ```
+/// # use ndarray::Array3;
+/// # use ndarray_ndimage::local_histogram;
+/// let data = Array3::<u8>::zeros((5, 6, 7));
+/// let footprint = Array3::from_elem((3, 3, 3), true);
+/// let mut histograms = local_histogram(&data, &footprint);
+/// while let Some(histogram) = histograms.next_histogram() {
+///     assert_eq!(histogram.bins()[0], histogram.total());
+/// }
+/// ```
+pub struct LocalHistograms {
+    bins: Vec<usize>,
+    shape: Vec<usize>,
+    strides: Vec<isize>,
+    /// Position of the voxels of the footprint, relative to its center
+    window: Vec<Vec<isize>>,
+    /// Voxels entering the window when it moves along the last axis, relative to the old center
+    entering: Vec<Vec<isize>>,
+    /// Voxels leaving the window when it moves along the last axis, relative to the old center
+    leaving: Vec<Vec<isize>>,
+    histogram: Histogram,
+    /// Index of the voxel of the current histogram
+    current: Option<Vec<usize>>,
+    done: bool,
+}
+
+impl LocalHistograms {
+    /// Returns the histogram of the window centered on the next voxel, or `None` when all voxels
+    /// have been visited.
+    pub fn next_histogram(&mut self) -> Option<&Histogram> {
+        if self.done || self.shape.contains(&0) {
+            return None;
+        }
+        match self.current.take() {
+            None => {
+                let start = vec![0; self.shape.len()];
+                self.update(&start, Which::Window, true);
+                self.current = Some(start);
+            }
+            Some(mut idx) => {
+                let last = self.shape.len().checked_sub(1)?;
+                if idx[last] + 1 < self.shape[last] {
+                    self.update(&idx, Which::Leaving, false);
+                    self.update(&idx, Which::Entering, true);
+                    idx[last] += 1;
+                } else {
+                    // New line, the histogram is rebuilt
+                    self.update(&idx, Which::Window, false);
+                    idx[last] = 0;
+                    let mut d = last;
+                    loop {
+                        if d == 0 {
+                            self.done = true;
+                            return None;
+                        }
+                        d -= 1;
+                        idx[d] += 1;
+                        if idx[d] < self.shape[d] {
+                            break;
+                        }
+                        idx[d] = 0;
+                    }
+                    self.update(&idx, Which::Window, true);
+                }
+                self.current = Some(idx);
+            }
+        }
+        Some(&self.histogram)
+    }
+
+    /// Adds or removes the voxels at `offsets` around `center` to the histogram. The voxels
+    /// outside of the image are ignored.
+    fn update(&mut self, center: &[usize], which: Which, add: bool) {
+        let offsets = match which {
+            Which::Window => &self.window,
+            Which::Entering => &self.entering,
+            Which::Leaving => &self.leaving,
+        };
+        'offsets: for offset in offsets {
+            let mut i = 0;
+            for d in 0..center.len() {
+                let c = center[d] as isize + offset[d];
+                if c < 0 || c >= self.shape[d] as isize {
+                    continue 'offsets;
+                }
+                i += c * self.strides[d];
+            }
+            let bin = self.bins[i as usize];
+            if add {
+                self.histogram.add(bin);
+            } else {
+                self.histogram.remove(bin);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Which {
+    Window,
+    Entering,
+    Leaving,
+}
+
+/// Returns the histograms of the windows centered on all voxels of `data`.
+///
+/// See [`LocalHistograms`](crate::LocalHistograms). This is the engine of the rank filters
+/// like [`entropy_filter`](crate::entropy_filter).
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - The shape of the window, centered on each voxel, same number of dimensions as
+///   `data`. The voxels outside of the image are ignored.
+pub fn local_histogram<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+) -> LocalHistograms
+where
+    A: HistogramType,
+    D: Dimension,
+{
+    let ndim = data.ndim();
+    let shape = data.shape().to_vec();
+    // All voxels are visited in logical order, so we can use the strides of a standard layout
+    let mut strides = vec![1; ndim];
+    for d in (0..ndim.saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1] as isize;
+    }
+    let bins = data.iter().map(|v| v.to_usize().unwrap()).collect();
+
+    let window: Vec<Vec<isize>> = footprint
+        .indexed_iter()
+        .filter(|(_, &f)| f)
+        .map(|(idx, _)| {
+            let idx = idx.into_dimension();
+            (0..ndim).map(|d| idx[d] as isize - (footprint.shape()[d] / 2) as isize).collect()
+        })
+        .collect();
+    let shifted = |o: &[isize], shift: isize| {
+        let mut o = o.to_vec();
+        o[ndim - 1] += shift;
+        o
+    };
+    let (mut entering, mut leaving) = (vec![], vec![]);
+    if ndim > 0 {
+        for o in &window {
+            if !window.contains(&shifted(o, 1)) {
+                entering.push(shifted(o, 1));
+            }
+            if !window.contains(&shifted(o, -1)) {
+                leaving.push(o.clone());
+            }
+        }
+    }
+
+    LocalHistograms {
+        bins,
+        shape,
+        strides,
+        window,
+        entering,
+        leaving,
+        histogram: Histogram::new(A::NB_BINS),
+        current: None,
+        done: false,
+    }
+}
+
+/// Local entropy filter.
+///
+/// Returns the Shannon entropy, in bits, of the values in the window centered on each voxel. It
+/// highlights the textured regions of an image. The voxels outside of the image are ignored.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - The shape of the window, centered on each voxel, same number of dimensions as
+///   `data`.
+pub fn entropy_filter<A, D>(data: &ArrayRef<A, D>, footprint: &ArrayRef<bool, D>) -> Array<f64, D>
+where
+    A: HistogramType,
+    D: Dimension,
+{
+    let mut histograms = local_histogram(data, footprint);
+    let mut entropies = Vec::with_capacity(data.len());
+    while let Some(histogram) = histograms.next_histogram() {
+        entropies.push(histogram.entropy());
+    }
+    Array::from_shape_vec(data.raw_dim(), entropies).unwrap()
+}
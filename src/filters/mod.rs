@@ -1,11 +1,19 @@
 use crate::PadMode;
 
 pub mod con_corr;
+pub mod eigen;
 pub mod gaussian;
+pub mod guided;
+pub mod hessian;
+pub mod histogram;
 pub mod median;
 pub mod min_max;
+pub mod non_local_means;
+pub mod ridges;
+pub mod structure_tensor;
 pub mod symmetry;
 pub mod uniform;
+pub mod unsharp;
 
 // TODO We might want to offer all NumPy mode (use PadMode instead)
 /// Method that will be used to determines how the input array is extended beyond its boundaries.
@@ -38,7 +46,7 @@ pub enum BorderMode<T> {
 }
 
 impl<T: Copy> BorderMode<T> {
-    fn to_pad_mode(&self) -> PadMode<T> {
+    pub(crate) fn to_pad_mode(&self) -> PadMode<T> {
         match *self {
             BorderMode::Constant(t) => PadMode::Constant(t),
             BorderMode::Nearest => PadMode::Edge,
@@ -0,0 +1,231 @@
+use ndarray::{s, Array3, ArrayRef3, ArrayView3, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{array_like, pad, uniform_filter, BorderMode};
+
+/// Non-local means denoising of a 3D image.
+///
+/// Each voxel is replaced by a weighted mean of the voxels in its search window. The weight of a
+/// candidate voxel is `exp(-d / h²)`, where `d` is the mean squared difference between the patch
+/// centered on the voxel and the patch centered on the candidate.
+///
+/// * `data` - The input 3D data.
+/// * `patch_size` - Length of the cubic patches used to compare voxels. Must be odd.
+/// * `search_radius` - Radius of the cubic search window.
+/// * `h` - Filtering parameter. A higher `h` removes more noise, but also more details.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+/// * `rician_sigma` - If `Some(sigma)`, the input is considered to be magnitude MR data with
+///   Rician noise of standard deviation `sigma`. The squared magnitudes are averaged and the bias
+///   `2 * sigma²` is removed before taking the square root.
+///
+/// **Panics** if `patch_size` is even or if `h` is not positive.
+pub fn non_local_means<A>(
+    data: &ArrayRef3<A>,
+    patch_size: usize,
+    search_radius: usize,
+    h: A,
+    mode: BorderMode<A>,
+    rician_sigma: Option<A>,
+) -> Array3<A>
+where
+    A: Float + FromPrimitive + 'static,
+{
+    check_parameters(patch_size, h);
+
+    let p_radius = patch_size / 2;
+    let s_radius = search_radius as isize;
+    let radius = p_radius + search_radius;
+    let padded = pad(data, &[[radius, radius]], mode.to_pad_mode());
+    let values = averaged_values(&padded, rician_sigma);
+    let h2 = h * h;
+
+    let (width, height, depth) = data.dim();
+    let zone = (width + 2 * p_radius, height + 2 * p_radius, depth + 2 * p_radius);
+    let center = padded.slice(s![
+        search_radius..search_radius + zone.0,
+        search_radius..search_radius + zone.1,
+        search_radius..search_radius + zone.2
+    ]);
+
+    // Instead of comparing all patches one by one, we compute the squared differences between the
+    // image and a shifted version of itself, for all shifts in the search window. A uniform filter
+    // on these differences then gives all the patch distances at once.
+    let mut diff = array_like(data, zone, A::zero());
+    let mut weights = array_like(data, data.dim(), A::zero());
+    let mut sum = array_like(data, data.dim(), A::zero());
+    for x in -s_radius..=s_radius {
+        for y in -s_radius..=s_radius {
+            for z in -s_radius..=s_radius {
+                let start = [x, y, z].map(|d| (s_radius + d) as usize);
+                let shifted = padded.slice(s![
+                    start[0]..start[0] + zone.0,
+                    start[1]..start[1] + zone.1,
+                    start[2]..start[2] + zone.2
+                ]);
+                Zip::from(&mut diff).and(&center).and(&shifted).for_each(|d, &a, &b| {
+                    let t = a - b;
+                    *d = t * t;
+                });
+
+                let distances = uniform_filter(&diff, patch_size, BorderMode::Nearest);
+                let distances = distances.slice(s![
+                    p_radius..p_radius + width,
+                    p_radius..p_radius + height,
+                    p_radius..p_radius + depth
+                ]);
+                let shifted_values = values.slice(s![
+                    p_radius + start[0]..p_radius + start[0] + width,
+                    p_radius + start[1]..p_radius + start[1] + height,
+                    p_radius + start[2]..p_radius + start[2] + depth
+                ]);
+                Zip::from(&mut weights)
+                    .and(&mut sum)
+                    .and(&distances)
+                    .and(&shifted_values)
+                    .for_each(|w, s, &d, &v| {
+                        let weight = (-d.max(A::zero()) / h2).exp();
+                        *w = *w + weight;
+                        *s = *s + weight * v;
+                    });
+            }
+        }
+    }
+
+    Zip::from(&mut sum).and(&weights).for_each(|s, &w| *s = *s / w);
+    unbias(sum, rician_sigma)
+}
+
+/// Blockwise non-local means denoising of a 3D image.
+///
+/// Faster variant of [`non_local_means`](crate::non_local_means) where the weighted means are
+/// computed on whole blocks centered on a sparse grid of voxels (one every `step` voxel on each
+/// axis), instead of on single voxels. Since the blocks overlap, each voxel receives several
+/// estimations, which are then averaged.
+///
+/// * `data` - The input 3D data.
+/// * `patch_size` - Length of the cubic blocks. Must be odd.
+/// * `search_radius` - Radius of the cubic search window.
+/// * `h` - Filtering parameter. A higher `h` removes more noise, but also more details.
+/// * `step` - Distance between the centers of two neighboring blocks. Must be in
+///   `1..=patch_size`, otherwise some voxels would not be covered.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+/// * `rician_sigma` - If `Some(sigma)`, the input is considered to be magnitude MR data with
+///   Rician noise of standard deviation `sigma`. See [`non_local_means`](crate::non_local_means).
+///
+/// **Panics** if `patch_size` is even, if `h` is not positive, if `step` is not in
+/// `1..=patch_size` or if `data` has an empty axis.
+pub fn non_local_means_blockwise<A>(
+    data: &ArrayRef3<A>,
+    patch_size: usize,
+    search_radius: usize,
+    h: A,
+    step: usize,
+    mode: BorderMode<A>,
+    rician_sigma: Option<A>,
+) -> Array3<A>
+where
+    A: Float + FromPrimitive + 'static,
+{
+    check_parameters(patch_size, h);
+    assert!(step > 0 && step <= patch_size, "`step` must be in `1..=patch_size`");
+
+    let p_radius = patch_size / 2;
+    let radius = p_radius + search_radius;
+    let padded = pad(data, &[[radius, radius]], mode.to_pad_mode());
+    let values = averaged_values(&padded, rician_sigma);
+    let h2 = h * h;
+    let nb_voxels = A::from_usize(patch_size.pow(3)).unwrap();
+
+    let (width, height, depth) = data.dim();
+    let centers =
+        [block_centers(width, step), block_centers(height, step), block_centers(depth, step)];
+    let mut estimation = Array3::zeros((patch_size, patch_size, patch_size));
+    let mut sum = array_like(data, data.dim(), A::zero());
+    let mut count = array_like(data, data.dim(), A::zero());
+    for &x in &centers[0] {
+        for &y in &centers[1] {
+            for &z in &centers[2] {
+                // Blocks are indexed by their first voxel in the padded image. The block centered
+                // on (x, y, z) starts at (x, y, z) + `search_radius`.
+                let block = block_at(&padded, [x, y, z].map(|c| c + search_radius), patch_size);
+
+                estimation.fill(A::zero());
+                let mut weights = A::zero();
+                for i in x..=x + 2 * search_radius {
+                    for j in y..=y + 2 * search_radius {
+                        for k in z..=z + 2 * search_radius {
+                            let other = block_at(&padded, [i, j, k], patch_size);
+                            let distance = Zip::from(&block)
+                                .and(&other)
+                                .fold(A::zero(), |acc, &a, &b| acc + (a - b) * (a - b))
+                                / nb_voxels;
+                            let weight = (-distance / h2).exp();
+                            weights = weights + weight;
+                            Zip::from(&mut estimation)
+                                .and(&block_at(&values, [i, j, k], patch_size))
+                                .for_each(|e, &v| *e = *e + weight * v);
+                        }
+                    }
+                }
+
+                // Aggregate the block estimation on all voxels it covers
+                for ((a, b, c), &e) in estimation.indexed_iter() {
+                    let idx = [x + a, y + b, z + c];
+                    if idx
+                        .iter()
+                        .zip([width, height, depth])
+                        .all(|(&i, len)| i >= p_radius && i < len + p_radius)
+                    {
+                        let idx = (idx[0] - p_radius, idx[1] - p_radius, idx[2] - p_radius);
+                        sum[idx] = sum[idx] + e / weights;
+                        count[idx] = count[idx] + A::one();
+                    }
+                }
+            }
+        }
+    }
+
+    Zip::from(&mut sum).and(&count).for_each(|s, &c| *s = *s / c);
+    unbias(sum, rician_sigma)
+}
+
+fn check_parameters<A: Float>(patch_size: usize, h: A) {
+    assert!(patch_size % 2 == 1, "`patch_size` must be odd");
+    assert!(h > A::zero(), "`h` must be positive");
+}
+
+/// Returns the values that will be averaged.
+///
+/// With Rician noise, the squared magnitudes are averaged instead of the magnitudes.
+fn averaged_values<A: Float>(padded: &Array3<A>, rician_sigma: Option<A>) -> Array3<A> {
+    match rician_sigma {
+        Some(_) => padded.mapv(|v| v * v),
+        None => padded.clone(),
+    }
+}
+
+/// Removes the Rician bias of the averaged squared magnitudes, if necessary.
+fn unbias<A: Float>(mut data: Array3<A>, rician_sigma: Option<A>) -> Array3<A> {
+    if let Some(sigma) = rician_sigma {
+        let bias = (sigma * sigma) + (sigma * sigma);
+        data.mapv_inplace(|v| (v - bias).max(A::zero()).sqrt());
+    }
+    data
+}
+
+/// Returns the cubic block of length `size` starting at `idx`.
+fn block_at<A>(arr: &Array3<A>, idx: [usize; 3], size: usize) -> ArrayView3<'_, A> {
+    arr.slice(s![idx[0]..idx[0] + size, idx[1]..idx[1] + size, idx[2]..idx[2] + size])
+}
+
+/// Returns the centers of the blocks along an axis. The last voxel is always a center.
+fn block_centers(len: usize, step: usize) -> Vec<usize> {
+    assert!(len > 0, "`data` must not have an empty axis");
+    let mut centers: Vec<_> = (0..len).step_by(step).collect();
+    if centers.last() != Some(&(len - 1)) {
+        centers.push(len - 1);
+    }
+    centers
+}
@@ -0,0 +1,190 @@
+use ndarray::{Array, ArrayRef, Dimension, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use super::{hessian::hessian_eigenvalues, symmetry::SymmetryStateCheck};
+use crate::hessian_matrix;
+
+/// Frangi vesselness filter.
+///
+/// Detects continuous ridges, like vessels, wrinkles and rivers, using the eigenvalues of the
+/// Hessian matrix. The filter is applied at all scales in `sigmas` and the maximal response is
+/// kept for each voxel. The eigenvalues are scale-normalized by `sigma²`.
+///
+/// * `data` - The input 2D or 3D data.
+/// * `sigmas` - Standard deviations of the Gaussian kernels, used as scales of the filter.
+/// * `alpha` - Sensitivity to deviation from a plate-like structure. Unused on 2D images.
+/// * `beta` - Sensitivity to deviation from a blob-like structure.
+/// * `gamma` - Sensitivity to areas of high variance/texture/structure. Defaults to half of the
+///   maximal Hessian norm of the first scale when `None`, and is then used for all scales.
+/// * `black_ridges` - When `true`, the filter detects black ridges on a bright background.
+///   Otherwise, it detects bright ridges on a dark background.
+///
+/// **Panics** if `data` is not 2D or 3D.
+pub fn frangi<A, D>(
+    data: &ArrayRef<A, D>,
+    sigmas: &[A],
+    alpha: A,
+    beta: A,
+    gamma: Option<A>,
+    black_ridges: bool,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let ndim = data.ndim();
+    assert!(ndim == 2 || ndim == 3, "Frangi filter is only defined on 2D or 3D data");
+
+    let two = A::from(2.0).unwrap();
+    let tiny = A::from(1e-10).unwrap();
+    let mut gamma = gamma;
+    filter_all_scales(data, sigmas, black_ridges, |eigenvalues, sigma| {
+        let sigma2 = sigma * sigma;
+        let norms = map_eigenvalues(eigenvalues, |e| {
+            e.iter().fold(A::zero(), |acc, &v| acc + (v * sigma2).powi(2)).sqrt()
+        });
+        // Like scikit-image, the default `gamma` is computed on the first scale only
+        let gamma = *gamma.get_or_insert_with(|| {
+            let max = norms.fold(A::zero(), |acc, &v| acc.max(v));
+            if max == A::zero() {
+                A::one()
+            } else {
+                max / two
+            }
+        });
+
+        let mut vesselness = map_eigenvalues(eigenvalues, |e| {
+            // Sort the eigenvalues by absolute value. Only the positive ones are interesting
+            // because we're looking for dark ridges. Clipping the others to a tiny value
+            // makes the blobness factor underflow to zero.
+            e.sort_unstable_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap());
+            let l1 = e[0].abs();
+            let (plate, blob) = if ndim == 2 {
+                (A::one(), l1 / e[1].max(tiny))
+            } else {
+                let (l2, l3) = (e[1].max(tiny), e[2].max(tiny));
+                let ra = l2 / l3;
+                (A::one() - (-ra * ra / (two * alpha * alpha)).exp(), l1 / (l2 * l3).sqrt())
+            };
+            plate * (-blob * blob / (two * beta * beta)).exp()
+        });
+        Zip::from(&mut vesselness).and(&norms).for_each(|v, &s| {
+            *v = *v * (A::one() - (-s * s / (two * gamma * gamma)).exp());
+        });
+        vesselness
+    })
+}
+
+/// Sato tubeness filter.
+///
+/// Detects continuous ridges, like tubes, using the eigenvalues of the Hessian matrix. The filter
+/// is applied at all scales in `sigmas` and the maximal response is kept for each voxel.
+///
+/// * `data` - The input N-D data.
+/// * `sigmas` - Standard deviations of the Gaussian kernels, used as scales of the filter.
+/// * `black_ridges` - When `true`, the filter detects black ridges on a bright background.
+///   Otherwise, it detects bright ridges on a dark background.
+pub fn sato<A, D>(data: &ArrayRef<A, D>, sigmas: &[A], black_ridges: bool) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let n = data.ndim();
+    let power = A::from(n - 1).unwrap().recip();
+    filter_all_scales(data, sigmas, black_ridges, |eigenvalues, sigma| {
+        // Geometric mean of all eigenvalues but the lowest one, clipped to 0
+        map_eigenvalues(eigenvalues, |e| {
+            let product = e[..n - 1].iter().fold(A::one(), |acc, &v| acc * v.max(A::zero()));
+            sigma * sigma * product.powf(power)
+        })
+    })
+}
+
+/// Meijering neuriteness filter.
+///
+/// Detects continuous ridges, like neurites, using the eigenvalues of the Hessian matrix. The
+/// filter is applied at all scales in `sigmas` and the maximal response is kept for each voxel.
+/// The response of each scale is normalized to a maximum of 1.
+///
+/// * `data` - The input N-D data.
+/// * `sigmas` - Standard deviations of the Gaussian kernels, used as scales of the filter.
+/// * `alpha` - Shaping filter constant, which selects maximally flat elongated features. Defaults
+///   to `1 / (ndim + 1)` when `None`.
+/// * `black_ridges` - When `true`, the filter detects black ridges on a bright background.
+///   Otherwise, it detects bright ridges on a dark background.
+pub fn meijering<A, D>(
+    data: &ArrayRef<A, D>,
+    sigmas: &[A],
+    alpha: Option<A>,
+    black_ridges: bool,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let alpha = alpha.unwrap_or_else(|| A::from(data.ndim() + 1).unwrap().recip());
+    filter_all_scales(data, sigmas, black_ridges, |eigenvalues, _| {
+        let mut neuriteness = map_eigenvalues(eigenvalues, |e| {
+            // Normalized eigenvalues `l_i = e_i + alpha * sum_{j != i} e_j`
+            let sum = e.iter().fold(A::zero(), |acc, &v| acc + v);
+            let largest = e.iter().map(|&v| v + alpha * (sum - v)).fold(A::zero(), |acc, v| {
+                if v.abs() > acc.abs() {
+                    v
+                } else {
+                    acc
+                }
+            });
+            largest.max(A::zero())
+        });
+        let max = neuriteness.fold(A::zero(), |acc, &v| acc.max(v));
+        if max > A::zero() {
+            neuriteness.mapv_inplace(|v| v / max);
+        }
+        neuriteness
+    })
+}
+
+/// Applies `filter` on the Hessian eigenvalues of all scales and keeps the maximal response.
+fn filter_all_scales<A, D, F>(
+    data: &ArrayRef<A, D>,
+    sigmas: &[A],
+    black_ridges: bool,
+    mut filter: F,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+    F: FnMut(&[Array<A, D>], A) -> Array<A, D>,
+{
+    // Normalize to black ridges
+    let data = if black_ridges { data.to_owned() } else { data.mapv(|v| -v) };
+
+    let mut output = Array::<A, D>::zeros(data.raw_dim());
+    for &sigma in sigmas {
+        let eigenvalues = hessian_eigenvalues(&hessian_matrix(&data, sigma, &[A::one()]));
+        let response = filter(&eigenvalues, sigma);
+        Zip::from(&mut output).and(&response).for_each(|o, &r| *o = o.max(r));
+    }
+    output
+}
+
+/// Builds an image by calling `f` on the eigenvalues of each voxel, sorted in decreasing order.
+fn map_eigenvalues<A, D, F>(eigenvalues: &[Array<A, D>], mut f: F) -> Array<A, D>
+where
+    A: Float,
+    D: Dimension,
+    F: FnMut(&mut [A]) -> A,
+{
+    let mut iters: Vec<_> = eigenvalues.iter().map(|e| e.iter()).collect();
+    let mut buffer = vec![A::zero(); eigenvalues.len()];
+    Array::from_shape_simple_fn(eigenvalues[0].raw_dim(), || {
+        for (b, iter) in buffer.iter_mut().zip(&mut iters) {
+            *b = *iter.next().unwrap();
+        }
+        f(&mut buffer)
+    })
+}
@@ -0,0 +1,109 @@
+use ndarray::{Array, ArrayRef, Dimension, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use super::{
+    eigen::{for_each_symmetric_matrix, symmetric_eigen},
+    gaussian::gaussian_filter_axes,
+    symmetry::SymmetryStateCheck,
+};
+use crate::BorderMode;
+
+type Images<A, D> = Vec<Array<A, D>>;
+
+/// Computes the structure tensor of a N-D image.
+///
+/// The structure tensor is the outer product of the image gradient with itself, smoothed by a
+/// Gaussian kernel. Its eigen-analysis describes the local orientation of the image.
+///
+/// Returns the `n * (n + 1) / 2` components of the upper triangle of the tensor, in row-major
+/// order. For example, for a 3D image, `[Axx, Axy, Axz, Ayy, Ayz, Azz]`.
+///
+/// * `data` - The input N-D data.
+/// * `sigma` - Standard deviation of the Gaussian derivative kernels used to compute the gradient
+///   (noise scale).
+/// * `rho` - Standard deviation of the Gaussian kernel used to smooth the outer products
+///   (integration scale).
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if one of the axis' lengths is lower than `4 * sigma + 0.5` or `4 * rho + 0.5`.
+pub fn structure_tensor<A, D>(
+    data: &ArrayRef<A, D>,
+    sigma: A,
+    rho: A,
+    mode: BorderMode<A>,
+) -> Vec<Array<A, D>>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    let n = data.ndim();
+    let sigmas = vec![sigma; n];
+    let rhos = vec![rho; n];
+    let no_derivative = vec![0; n];
+
+    let gradients: Vec<_> = (0..n)
+        .map(|d| {
+            let mut orders = vec![0; n];
+            orders[d] = 1;
+            gaussian_filter_axes(data, &sigmas, &orders, mode, 4)
+        })
+        .collect();
+
+    let mut components = Vec::with_capacity(n * (n + 1) / 2);
+    let mut product = Array::zeros(data.raw_dim());
+    for i in 0..n {
+        for j in i..n {
+            Zip::from(&mut product)
+                .and(&gradients[i])
+                .and(&gradients[j])
+                .for_each(|p, &gi, &gj| *p = gi * gj);
+            components.push(gaussian_filter_axes(&product, &rhos, &no_derivative, mode, 4));
+        }
+    }
+    components
+}
+
+/// Computes the eigenvalues and the principal eigenvector of the structure tensor of each voxel.
+///
+/// Returns the eigenvalues, as `n` images where the first one holds the greatest eigenvalue of
+/// each voxel and the last one holds the lowest eigenvalue, and the principal eigenvector field,
+/// as `n` images holding the components of the normalized eigenvector associated with the greatest
+/// eigenvalue. The principal eigenvector is the direction of the strongest intensity variation.
+/// Its sign is chosen so that its first non-zero component is positive.
+///
+/// * `tensor` - The components of the structure tensor, as returned by
+///   [`structure_tensor`](crate::structure_tensor).
+///
+/// **Panics** if the number of components is not `n * (n + 1) / 2`.
+pub fn structure_tensor_eigenvalues<A, D>(tensor: &[Array<A, D>]) -> (Images<A, D>, Images<A, D>)
+where
+    A: Float,
+    D: Dimension,
+{
+    let n = tensor[0].ndim();
+    let len = tensor[0].len();
+    let mut eigenvalues = vec![Vec::with_capacity(len); n];
+    let mut principal = vec![Vec::with_capacity(len); n];
+    let mut values = vec![A::zero(); n];
+    let mut vectors = vec![A::zero(); n * n];
+    for_each_symmetric_matrix(tensor, |matrix| {
+        symmetric_eigen(matrix, n, &mut values, &mut vectors);
+        for (e, &v) in eigenvalues.iter_mut().zip(&values) {
+            e.push(v);
+        }
+
+        // The eigenvectors are the columns of `vectors`
+        let first = (0..n).map(|k| vectors[k * n]).find(|&v| v != A::zero());
+        let sign = if first.unwrap_or(A::one()) < A::zero() { -A::one() } else { A::one() };
+        for (k, p) in principal.iter_mut().enumerate() {
+            p.push(sign * vectors[k * n]);
+        }
+    });
+
+    let to_arrays = |images: Vec<Vec<A>>| -> Images<A, D> {
+        images.into_iter().map(|e| Array::from_shape_vec(tensor[0].raw_dim(), e).unwrap()).collect()
+    };
+    (to_arrays(eigenvalues), to_arrays(principal))
+}
@@ -0,0 +1,60 @@
+use ndarray::{Array, ArrayRef, Dimension, Zip};
+use num_traits::{Bounded, FromPrimitive, ToPrimitive};
+
+use crate::{gaussian_filter, is_integer, BorderMode};
+
+/// Sharpens an image with unsharp masking.
+///
+/// The blurred image (Gaussian filter of standard deviation `sigma`) is subtracted from the
+/// original image to get the details, which are then added back, scaled by `amount`:
+///
+/// `output = data + amount * (data - blurred)`
+///
+/// This is also known as high-boost sharpening. The input `data` is processed in `f64`, then the
+/// result is clipped to the range of `A` and converted back. Integer results are rounded.
+///
+/// * `data` - The input N-D data.
+/// * `sigma` - Standard deviation of the Gaussian kernel used to blur the image.
+/// * `amount` - The scale of the details added back to the image. `0.0` returns the input.
+/// * `threshold` - The details with an absolute value lower than `threshold` are ignored, to avoid
+///   sharpening the noise. Use `0.0` to sharpen everything.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if one of the axis' lengths is lower than `4 * sigma + 0.5`.
+pub fn unsharp_mask<A, D>(
+    data: &ArrayRef<A, D>,
+    sigma: f64,
+    amount: f64,
+    threshold: f64,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Copy + FromPrimitive + ToPrimitive + Bounded,
+    D: Dimension,
+{
+    let mode = match mode {
+        BorderMode::Constant(t) => BorderMode::Constant(t.to_f64().unwrap()),
+        BorderMode::Mirror => BorderMode::Mirror,
+        BorderMode::Nearest => BorderMode::Nearest,
+        BorderMode::Reflect => BorderMode::Reflect,
+        BorderMode::Wrap => BorderMode::Wrap,
+    };
+    let values = data.map(|v| v.to_f64().unwrap());
+    let blurred = gaussian_filter(&values, sigma, 0, mode, 4);
+
+    let is_integer = is_integer::<A>();
+    let (min, max) = (A::min_value().to_f64().unwrap(), A::max_value().to_f64().unwrap());
+    Zip::from(&values).and(&blurred).map_collect(|&v, &b| {
+        let details = v - b;
+        let sharpened =
+            if details.abs() >= threshold { v + amount * details } else { v }.clamp(min, max);
+        let sharpened = if is_integer { sharpened.round() } else { sharpened };
+        // The bounds of the 64-bit integers are rounded outside of their range in `f64`
+        A::from_f64(sharpened).unwrap_or(if sharpened > 0.0 {
+            A::max_value()
+        } else {
+            A::min_value()
+        })
+    })
+}
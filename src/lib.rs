@@ -3,29 +3,71 @@
 //! The `ndarray-image` crate provides multidimensional image processing for `ArrayBase`,
 //! the *n*-dimensional array data structure provided by [`ndarray`].
 
+use std::borrow::Cow;
+
 use ndarray::{arr3, Array, Array3, ArrayRef, ArrayRef3, Dimension, ShapeBuilder};
+use num_traits::{FromPrimitive, ToPrimitive};
 
+mod exposure;
+mod features;
 mod filters;
 mod interpolation;
 mod measurements;
 mod morphology;
 mod pad;
+mod restoration;
+mod threshold;
 
+pub use exposure::{
+    equalization::{equalize_adapthist, equalize_hist},
+    intensity::{normalize_zscore, rescale_intensity, IntensityRange},
+    matching::{match_histograms, HistogramMatchingMethod},
+};
+pub use features::{
+    blob::{blob_dog, blob_log, Blob},
+    canny::canny,
+};
 pub use filters::{
     con_corr::{convolve, convolve1d, correlate, correlate1d, prewitt, sobel},
-    gaussian::{gaussian_filter, gaussian_filter1d},
+    gaussian::{difference_of_gaussians, gaussian_filter, gaussian_filter1d},
+    guided::guided_filter,
+    hessian::{hessian_eigenvalues, hessian_matrix},
+    histogram::{entropy_filter, local_histogram, Histogram, HistogramType, LocalHistograms},
     median::median_filter,
     min_max::{
         maximum_filter, maximum_filter1d, maximum_filter1d_to, minimum_filter, minimum_filter1d,
         minimum_filter1d_to,
     },
+    non_local_means::{non_local_means, non_local_means_blockwise},
+    ridges::{frangi, meijering, sato},
+    structure_tensor::{structure_tensor, structure_tensor_eigenvalues},
     uniform::{uniform_filter, uniform_filter1d},
+    unsharp::unsharp_mask,
     BorderMode,
 };
 pub use interpolation::{shift, spline_filter, spline_filter1d, zoom};
 pub use measurements::{label, label_histogram, largest_connected_components, most_frequent_label};
-pub use morphology::{binary_closing, binary_dilation, binary_erosion, binary_opening};
+pub use morphology::{
+    binary_black_tophat, binary_closing, binary_dilation, binary_dilation_until_stable,
+    binary_erosion, binary_erosion_until_stable, binary_fill_holes, binary_fill_holes_slicewise,
+    binary_hit_or_miss, binary_morphological_gradient, binary_opening, binary_propagation,
+    binary_white_tophat, black_tophat, distance_transform_edt, grey_closing, grey_dilation,
+    grey_erosion, grey_opening, medial_axis, morphological_gradient, morphological_laplace,
+    reconstruction, skeleton_graph, skeletonize_3d, white_tophat, ReconstructionMethod,
+    SkeletonBranch, SkeletonGraph,
+};
 pub use pad::{pad, pad_to, PadMode};
+pub use restoration::{
+    deconvolution::{richardson_lucy, wiener},
+    total_variation::{denoise_tv, denoise_tv_bregman},
+};
+pub use threshold::{
+    global::{
+        threshold_isodata, threshold_li, threshold_mean, threshold_multiotsu, threshold_otsu,
+        threshold_triangle, threshold_yen,
+    },
+    local::{threshold_local, threshold_niblack, threshold_sauvola, LocalThresholdMethod},
+};
 
 /// 3D mask
 pub type Mask = Array3<bool>;
@@ -91,6 +133,26 @@ where
     (width - n, height - n, depth - n)
 }
 
+/// Returns the spacing of all axes. A single value can be given for all axes.
+fn read_spacing<A: Copy>(nb_dim: usize, spacing: &[A]) -> Cow<'_, [A]> {
+    if spacing.len() == 1 && spacing.len() < nb_dim {
+        // The user provided a single spacing for all dimensions
+        Cow::from(vec![spacing[0]; nb_dim])
+    } else if spacing.len() == nb_dim {
+        Cow::from(spacing)
+    } else {
+        panic!("Inconsistant number of dimensions and spacing values");
+    }
+}
+
+/// Returns `true` if `A` is an integer type, i.e. if `0.5` doesn't survive a round-trip in `A`.
+fn is_integer<A>() -> bool
+where
+    A: FromPrimitive + ToPrimitive,
+{
+    A::from_f64(0.5).and_then(|v| v.to_f64()) != Some(0.5)
+}
+
 // TODO Use x.round_ties_even() when available on stable
 // https://github.com/rust-lang/rust/issues/96710
 fn round_ties_even(x: f64) -> f64 {
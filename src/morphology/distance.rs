@@ -0,0 +1,126 @@
+use ndarray::{Array, ArrayRef, Axis, Dimension, Zip};
+
+use crate::read_spacing;
+
+type Indices<D> = Array<usize, <D as Dimension>::Larger>;
+
+/// Exact Euclidean distance transform of a N-D binary image.
+///
+/// Computes the distance of each `true` voxel to the closest `false` voxel. The `false` voxels
+/// have a distance of `0`. This is the separable linear-time algorithm of P. Felzenszwalb and
+/// D. Huttenlocher (2012), which computes the lower envelope of parabolas along each axis in turn.
+///
+/// Returns the distance map and, if `return_indices` is `true`, the feature transform. It's an
+/// array of shape `(ndim, ...mask.shape())` holding the coordinates of the closest `false` voxel
+/// of each voxel, like SciPy. When there's no `false` voxel, the distances are infinite and the
+/// indices are `usize::MAX`.
+///
+/// * `mask` - Binary image. The distance of its `true` voxels is computed.
+/// * `sampling` - Spacing of the voxels along each axis. A single value can be given for all axes.
+/// * `return_indices` - Whether to compute the feature transform.
+///
+/// **Panics** if `sampling` doesn't have 1 or `mask.ndim()` values.
+pub fn distance_transform_edt<D>(
+    mask: &ArrayRef<bool, D>,
+    sampling: &[f64],
+    return_indices: bool,
+) -> (Array<f64, D>, Option<Indices<D>>)
+where
+    D: Dimension,
+{
+    let sampling = read_spacing(mask.ndim(), sampling);
+
+    // The closest `false` voxel is tracked with its index in the logical order
+    let mut distance = mask.mapv(|m| if m { f64::INFINITY } else { 0.0 });
+    let mut features = Array::from_shape_vec(mask.raw_dim(), (0..mask.len()).collect()).unwrap();
+    Zip::from(&mut features).and(mask).for_each(|f, &m| {
+        if m {
+            *f = usize::MAX;
+        }
+    });
+
+    let (mut f, mut lane_features, mut out, mut vertices) = (vec![], vec![], vec![], vec![]);
+    for (d, &spacing) in sampling.iter().enumerate() {
+        Zip::from(distance.lanes_mut(Axis(d))).and(features.lanes_mut(Axis(d))).for_each(
+            |mut distance, mut features| {
+                f.clear();
+                f.extend(distance.iter().cloned());
+                lane_features.clear();
+                lane_features.extend(features.iter().cloned());
+                lower_envelope(&f, spacing, &mut out, &mut vertices);
+                for (q, (dist, feature)) in distance.iter_mut().zip(features.iter_mut()).enumerate()
+                {
+                    *dist = out[q];
+                    *feature = vertices.get(q).map_or(usize::MAX, |&p| lane_features[p]);
+                }
+            },
+        );
+    }
+    distance.mapv_inplace(f64::sqrt);
+
+    let indices = return_indices.then(|| {
+        let shape = mask.shape();
+        let mut indices_dim = D::Larger::zeros(mask.ndim() + 1);
+        indices_dim[0] = mask.ndim();
+        indices_dim.slice_mut()[1..].copy_from_slice(shape);
+        let mut indices = Vec::with_capacity(mask.ndim() * mask.len());
+        for d in 0..mask.ndim() {
+            let inner: usize = shape[d + 1..].iter().product();
+            indices.extend(features.iter().map(|&feature| {
+                if feature == usize::MAX {
+                    usize::MAX
+                } else {
+                    feature / inner % shape[d]
+                }
+            }));
+        }
+        Array::from_shape_vec(indices_dim, indices).unwrap()
+    });
+    (distance, indices)
+}
+
+/// 1D squared distance transform of the sampled function `f`.
+///
+/// The result is written in `out` and the index of the closest vertex in `vertices`. `vertices` is
+/// empty if all values of `f` are infinite.
+fn lower_envelope(f: &[f64], spacing: f64, out: &mut Vec<f64>, vertices: &mut Vec<usize>) {
+    out.clear();
+    vertices.clear();
+    let position = |p: usize| p as f64 * spacing;
+
+    // The parabolas of the lower envelope and the start of their range
+    let (mut parabolas, mut starts): (Vec<usize>, Vec<f64>) = (vec![], vec![]);
+    for q in (0..f.len()).filter(|&q| f[q].is_finite()) {
+        let height = |p: usize| f[p] + position(p) * position(p);
+        while let Some(&p) = parabolas.last() {
+            let s = (height(q) - height(p)) / (2.0 * (position(q) - position(p)));
+            if s <= *starts.last().unwrap() {
+                parabolas.pop();
+                starts.pop();
+            } else {
+                parabolas.push(q);
+                starts.push(s);
+                break;
+            }
+        }
+        if parabolas.is_empty() {
+            parabolas.push(q);
+            starts.push(f64::NEG_INFINITY);
+        }
+    }
+    if parabolas.is_empty() {
+        out.resize(f.len(), f64::INFINITY);
+        return;
+    }
+
+    let mut k = 0;
+    for q in 0..f.len() {
+        while k + 1 < parabolas.len() && starts[k + 1] < position(q) {
+            k += 1;
+        }
+        let p = parabolas[k];
+        let delta = position(q) - position(p);
+        out.push(delta * delta + f[p]);
+        vertices.push(p);
+    }
+}
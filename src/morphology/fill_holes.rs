@@ -0,0 +1,48 @@
+use ndarray::{Array, ArrayRef, Axis, Dimension, RemoveAxis};
+
+use crate::binary_propagation;
+
+/// Fills the holes of a N-D binary image.
+///
+/// The holes are the background regions that are not connected to the border of the image. The
+/// background is propagated from the border, then the voxels that were not reached are filled.
+///
+/// * `mask` - Binary image whose holes will be filled.
+/// * `kernel` - Structuring element defining the connectivity of the background. For example, in
+///   3D, [`Kernel3d::Star`](crate::Kernel3d) fills the holes that are only connected to the
+///   border by an edge or a corner, while [`Kernel3d::Full`](crate::Kernel3d) doesn't.
+pub fn binary_fill_holes<D>(mask: &ArrayRef<bool, D>, kernel: &ArrayRef<bool, D>) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    let background = mask.mapv(|m| !m);
+    let seed = Array::from_elem(mask.raw_dim(), false);
+    let mut filled = binary_propagation(&seed, &background, kernel, true);
+    filled.mapv_inplace(|f| !f);
+    filled
+}
+
+/// Fills the holes of each slice of a N-D binary image.
+///
+/// Each slice along `axis` is filled independently with
+/// [`binary_fill_holes`](crate::binary_fill_holes). This is useful when the objects are closed
+/// in 2D, but open on the first or last slice of the volume.
+///
+/// * `mask` - Binary image whose holes will be filled.
+/// * `kernel` - Structuring element of dimension `mask.ndim() - 1`, defining the connectivity of
+///   the background in the slices.
+/// * `axis` - The axis along which the slices are taken.
+pub fn binary_fill_holes_slicewise<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D::Smaller>,
+    axis: Axis,
+) -> Array<bool, D>
+where
+    D: Dimension + RemoveAxis,
+{
+    let mut filled = mask.to_owned();
+    for (slice, mut out) in mask.axis_iter(axis).zip(filled.axis_iter_mut(axis)) {
+        out.assign(&binary_fill_holes(&slice, kernel));
+    }
+    filled
+}
@@ -0,0 +1,339 @@
+use ndarray::{indices, Array, ArrayRef, Dimension, IxDyn, Zip};
+use num_traits::{Bounded, FromPrimitive, Num, ToPrimitive};
+
+use crate::{pad, BorderMode};
+
+/// Greyscale erosion of a N-D image.
+///
+/// Each voxel is replaced by the minimum of `data[x + k] - structure[k]`, for all the positions `k`
+/// of the footprint. It's a minimum filter when `structure` is `None`.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood used for the erosion. The center is at
+///   `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`. The
+///   structuring element is flat (all zeros) when `None`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn grey_erosion<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    grey_filter(data, footprint, structure, mode, false)
+}
+
+/// Greyscale dilation of a N-D image.
+///
+/// Each voxel is replaced by the maximum of `data[x - k] + structure[k]`, for all the positions `k`
+/// of the footprint. It's a maximum filter with a reflected footprint when `structure` is `None`.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood used for the dilation. The center is at
+///   `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`. The
+///   structuring element is flat (all zeros) when `None`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn grey_dilation<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    grey_filter(data, footprint, structure, mode, true)
+}
+
+/// Greyscale opening of a N-D image.
+///
+/// The opening is the dilation of the erosion of the image. It removes the bright details smaller
+/// than the footprint.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn grey_opening<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let eroded = grey_erosion(data, footprint, structure, mode);
+    grey_dilation(&eroded, footprint, structure, mode)
+}
+
+/// Greyscale closing of a N-D image.
+///
+/// The closing is the erosion of the dilation of the image. It removes the dark details smaller
+/// than the footprint.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn grey_closing<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let dilated = grey_dilation(data, footprint, structure, mode);
+    grey_erosion(&dilated, footprint, structure, mode)
+}
+
+/// Common function of `grey_erosion` and `grey_dilation`.
+fn grey_filter<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+    is_dilation: bool,
+) -> Array<A, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let ndim = data.ndim();
+    assert_eq!(footprint.ndim(), ndim, "`footprint` must have the same dimension as `data`");
+    if let Some(structure) = structure {
+        assert_eq!(
+            structure.shape(),
+            footprint.shape(),
+            "`structure` must have the same shape as `footprint`"
+        );
+    }
+
+    // The neighbor of the position `k` is at `k - center` for the erosion and at `center - k` for
+    // the dilation, so the padding is reversed.
+    let padding: Vec<_> = footprint
+        .shape()
+        .iter()
+        .map(|&len| {
+            let (before, after) = (len / 2, len - 1 - len / 2);
+            if is_dilation {
+                [after, before]
+            } else {
+                [before, after]
+            }
+        })
+        .collect();
+    let padded = pad(data, &padding, mode.to_pad_mode());
+    let padded = padded.view().into_dyn();
+
+    // Offsets of the neighbors in the padded image, relative to the first voxel of the window
+    let strides = padded.strides();
+    let footprint = footprint.view().into_dyn();
+    let structure = structure.map(|s| s.view().into_dyn());
+    let neighbors: Vec<(isize, A)> = footprint
+        .indexed_iter()
+        .filter(|&(_, &f)| f)
+        .map(|(idx, _)| {
+            let offset = (0..ndim).fold(0, |acc, d| {
+                let center = footprint.shape()[d] / 2;
+                let pos = if is_dilation {
+                    padding[d][0] + center - idx[d]
+                } else {
+                    padding[d][0] + idx[d] - center
+                };
+                acc + pos as isize * strides[d]
+            });
+            let weight = structure.as_ref().map_or(A::zero(), |s| s[&idx]);
+            (offset, weight)
+        })
+        .collect();
+    assert!(!neighbors.is_empty(), "`footprint` must have at least one `true` value");
+
+    let padded = padded.as_slice_memory_order().unwrap();
+    let is_integer = crate::is_integer::<A>();
+    let filtered = indices(IxDyn(data.shape()))
+        .into_iter()
+        .map(|idx| {
+            let start = (0..ndim).fold(0, |acc, d| acc + idx[d] as isize * strides[d]);
+            let mut values = neighbors.iter().map(|&(offset, weight)| {
+                apply_weight(padded[(start + offset) as usize], weight, is_dilation, is_integer)
+            });
+            let first = values.next().unwrap();
+            values.fold(first, |acc, v| {
+                if (is_dilation && v > acc) || (!is_dilation && v < acc) {
+                    v
+                } else {
+                    acc
+                }
+            })
+        })
+        .collect();
+    Array::from_shape_vec(data.raw_dim(), filtered).unwrap()
+}
+
+/// Returns `v + weight` for the dilation and `v - weight` for the erosion. Integer types saturate
+/// instead of overflowing.
+fn apply_weight<A>(v: A, weight: A, is_dilation: bool, is_integer: bool) -> A
+where
+    A: Copy + Num + PartialOrd + Bounded,
+{
+    let (min, max) = (A::min_value(), A::max_value());
+    let (positive, negative) = (weight > A::zero(), weight < A::zero());
+    if is_dilation {
+        if is_integer && positive && v > max - weight {
+            max
+        } else if is_integer && negative && v < min - weight {
+            min
+        } else {
+            v + weight
+        }
+    } else if is_integer && positive && v < min + weight {
+        min
+    } else if is_integer && negative && v > max + weight {
+        max
+    } else {
+        v - weight
+    }
+}
+
+/// White top-hat of a N-D image.
+///
+/// The white top-hat is the difference between the image and its opening. It keeps the bright
+/// details smaller than the footprint and suppresses the background. It's computed in `f64`
+/// because it can be negative with a non-flat `structure` or an asymmetric `footprint`.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn white_tophat<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<f64, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let opened = grey_opening(data, footprint, structure, mode);
+    Zip::from(data).and(&opened).map_collect(|&d, &o| d.to_f64().unwrap() - o.to_f64().unwrap())
+}
+
+/// Black top-hat of a N-D image.
+///
+/// The black top-hat is the difference between the closing of the image and the image. It keeps
+/// the dark details smaller than the footprint. It's computed in `f64` because it can be negative
+/// with a non-flat `structure` or an asymmetric `footprint`.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn black_tophat<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<f64, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let closed = grey_closing(data, footprint, structure, mode);
+    Zip::from(&closed).and(data).map_collect(|&c, &d| c.to_f64().unwrap() - d.to_f64().unwrap())
+}
+
+/// Morphological gradient of a N-D image.
+///
+/// The morphological gradient is the difference between the dilation and the erosion of the
+/// image. It's high on the boundaries of the objects. It's computed in `f64` because it can be
+/// negative with a non-flat `structure` or a `footprint` which doesn't contain its center.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn morphological_gradient<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<f64, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let dilated = grey_dilation(data, footprint, structure, mode);
+    let eroded = grey_erosion(data, footprint, structure, mode);
+    Zip::from(&dilated).and(&eroded).map_collect(|&d, &e| d.to_f64().unwrap() - e.to_f64().unwrap())
+}
+
+/// Morphological laplace of a N-D image.
+///
+/// The morphological laplace is `dilation + erosion - 2 * data`. It's positive on the dark side of
+/// the boundaries and negative on the bright side, so it's computed in `f64`, even for unsigned
+/// types.
+///
+/// * `data` - The input N-D data.
+/// * `footprint` - Positions of the neighborhood. The center is at `len / 2` on all axes.
+/// * `structure` - Optional non-flat structuring element, of the same shape as `footprint`.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `footprint` has no `true` value or if `structure` doesn't have the same shape as
+/// `footprint`.
+pub fn morphological_laplace<A, D>(
+    data: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    structure: Option<&ArrayRef<A, D>>,
+    mode: BorderMode<A>,
+) -> Array<f64, D>
+where
+    A: Copy + Num + PartialOrd + Bounded + FromPrimitive + ToPrimitive,
+    D: Dimension,
+{
+    let dilated = grey_dilation(data, footprint, structure, mode);
+    let eroded = grey_erosion(data, footprint, structure, mode);
+    Zip::from(&dilated).and(&eroded).and(data).map_collect(|&di, &e, &d| {
+        di.to_f64().unwrap() + e.to_f64().unwrap() - 2.0 * d.to_f64().unwrap()
+    })
+}
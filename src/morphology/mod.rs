@@ -1,79 +1,175 @@
+mod distance;
+mod fill_holes;
+mod grey;
 mod offsets;
+mod reconstruction;
+mod skeleton;
 
-use ndarray::{Array3, ArrayRef3, ArrayView3, ArrayViewMut3};
+use ndarray::{Array, ArrayRef, Dimension, IxDyn, Zip};
 
-use crate::Mask;
+pub use distance::distance_transform_edt;
+pub use fill_holes::{binary_fill_holes, binary_fill_holes_slicewise};
+pub use grey::{
+    black_tophat, grey_closing, grey_dilation, grey_erosion, grey_opening, morphological_gradient,
+    morphological_laplace, white_tophat,
+};
 use offsets::Offsets;
+pub use reconstruction::{binary_propagation, reconstruction, ReconstructionMethod};
+pub use skeleton::{medial_axis, skeleton_graph, skeletonize_3d, SkeletonBranch, SkeletonGraph};
 
-/// Binary erosion of a 3D binary image.
+/// Binary erosion of a N-D binary image.
 ///
-/// * `mask` - Binary image to be eroded.
-/// * `kernel` - Structuring element used for the erosion. Must be of odd length. The center must
-///   be `true`.
-/// * `iterations` - The erosion is repeated iterations times.
-pub fn binary_erosion(
-    mask: &ArrayRef3<bool>,
-    kernel: &ArrayRef3<bool>,
+/// * `data` - Binary image to be eroded.
+/// * `kernel` - Structuring element used for the erosion. The center must be `true`.
+/// * `iterations` - The erosion is repeated iterations times. See
+///   [`binary_erosion_until_stable`](crate::binary_erosion_until_stable) to repeat it until
+///   the result doesn't change anymore.
+/// * `mask` - Optional mask. Only the voxels where `mask` is `true` are modified at each
+///   iteration.
+/// * `border_value` - Value of the voxels outside of the image. `true` avoids *border effects*,
+///   `false` erodes the objects touching the border, like SciPy's default.
+/// * `origin` - Placement of the kernel on the voxels, on each axis. A single value can be given
+///   for all axes. `0` centers the kernel at `len / 2`, positive values shift it to the left.
+///
+/// **Panics** if `iterations` is 0, if `mask` doesn't have the same shape as `data` or if `origin`
+/// moves the center outside of the kernel.
+pub fn binary_erosion<D>(
+    data: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
     iterations: usize,
-) -> Mask
+    mask: Option<&ArrayRef<bool, D>>,
+    border_value: bool,
+    origin: &[isize],
+) -> Array<bool, D>
+where
+    D: Dimension,
 {
-    mask.as_slice_memory_order()
-        .expect("Morphological operations can only be called on arrays with contiguous memory.");
+    assert!(iterations > 0, "`iterations` must be greater than 0");
+    erode_or_dilate(data, kernel, iterations, mask, border_value, origin, false).0
+}
 
-    // We can't really reserve a good number of elements here. It could be anything.
-    let mut last_indices = (iterations > 1).then_some(vec![]);
-    let mut eroded = mask.to_owned();
-    let mut offsets = Offsets::new(mask, kernel.view(), false);
-    erode(mask.view(), &mut eroded.view_mut(), &mut offsets, &mut last_indices);
+/// Binary erosion of a N-D binary image, repeated until the result doesn't change anymore.
+///
+/// Returns the eroded image and the number of iterations that modified it.
+///
+/// See [`binary_erosion`](crate::binary_erosion) for the description of the arguments.
+pub fn binary_erosion_until_stable<D>(
+    data: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    mask: Option<&ArrayRef<bool, D>>,
+    border_value: bool,
+    origin: &[isize],
+) -> (Array<bool, D>, usize)
+where
+    D: Dimension,
+{
+    erode_or_dilate(data, kernel, 0, mask, border_value, origin, false)
+}
 
-    if let Some(mut last_indices) = last_indices {
-        for it in 1..iterations {
-            if last_indices.is_empty() {
-                break;
-            }
+/// Binary dilation of a N-D binary image.
+///
+/// * `data` - Binary image to be dilated.
+/// * `kernel` - Structuring element used for the dilation. The center must be `true`.
+/// * `iterations` - The dilation is repeated iterations times. See
+///   [`binary_dilation_until_stable`](crate::binary_dilation_until_stable) to repeat it until
+///   the result doesn't change anymore.
+/// * `mask` - Optional mask. Only the voxels where `mask` is `true` are modified at each
+///   iteration.
+/// * `border_value` - Value of the voxels outside of the image. `false` avoids dilating the
+///   borders, like SciPy's default.
+/// * `origin` - Placement of the kernel on the voxels, on each axis. A single value can be given
+///   for all axes. `0` centers the kernel at `len / 2`, positive values shift it to the left.
+///
+/// **Panics** if `iterations` is 0, if `mask` doesn't have the same shape as `data` or if `origin`
+/// moves the center outside of the kernel.
+pub fn binary_dilation<D>(
+    data: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    iterations: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+    border_value: bool,
+    origin: &[isize],
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    assert!(iterations > 0, "`iterations` must be greater than 0");
+    erode_or_dilate(data, kernel, iterations, mask, border_value, origin, true).0
+}
 
-            let save_next_indices = it < iterations - 1;
-            next_it(&mut eroded, &mut offsets, &mut last_indices, save_next_indices, true, false);
-        }
-    }
-    eroded
+/// Binary dilation of a N-D binary image, repeated until the result doesn't change anymore.
+///
+/// Returns the dilated image and the number of iterations that modified it.
+///
+/// See [`binary_dilation`](crate::binary_dilation) for the description of the arguments.
+pub fn binary_dilation_until_stable<D>(
+    data: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    mask: Option<&ArrayRef<bool, D>>,
+    border_value: bool,
+    origin: &[isize],
+) -> (Array<bool, D>, usize)
+where
+    D: Dimension,
+{
+    erode_or_dilate(data, kernel, 0, mask, border_value, origin, true)
 }
 
-/// Binary dilation of a 3D binary image.
+/// Common function of the binary erosion and dilation.
 ///
-/// * `mask` - Binary image to be dilated.
-/// * `kernel` - Structuring element used for the erosion. Must be of odd length. The center must
-///   be `true`.
-/// * `iterations` - The dilation is repeated iterations times.
-pub fn binary_dilation(
-    mask: &ArrayRef3<bool>,
-    kernel: &ArrayRef3<bool>,
+/// `iterations = 0` repeats the operation until the result doesn't change anymore. The number of
+/// iterations that modified the image is only exact in this mode.
+fn erode_or_dilate<D>(
+    data: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
     iterations: usize,
-) -> Mask
+    mask: Option<&ArrayRef<bool, D>>,
+    border_value: bool,
+    origin: &[isize],
+    is_dilate: bool,
+) -> (Array<bool, D>, usize)
+where
+    D: Dimension,
 {
-    mask.as_slice_memory_order()
+    data.as_slice_memory_order()
         .expect("Morphological operations can only be called on arrays with contiguous memory.");
+    let allowed = mask.map(|mask| same_layout(data, mask));
+    let allowed = allowed.as_ref().map(|a| a.as_slice_memory_order().unwrap());
+    let until_stable = iterations == 0;
 
     // We can't really reserve a good number of elements here. It could be anything.
-    let mut last_indices = (iterations > 1).then_some(vec![]);
-    let mut dilated = mask.to_owned();
-    let mut offsets = Offsets::new(mask, kernel.view(), true);
-    dilate(mask.view(), &mut dilated, &mut offsets, &mut last_indices);
+    let mut last_indices = (until_stable || iterations > 1).then_some(vec![]);
+    let mut out = data.to_owned();
+    let mut offsets = Offsets::new(data, kernel.view(), origin, is_dilate);
+    if is_dilate {
+        dilate(data, &mut out, &mut offsets, allowed, border_value, &mut last_indices);
+    } else {
+        erode(data, &mut out, &mut offsets, allowed, border_value, &mut last_indices);
+    }
 
+    let mut it = 1;
     if let Some(mut last_indices) = last_indices {
-        for it in 1..iterations {
-            if last_indices.is_empty() {
-                break;
-            }
-
-            let save_next_indices = it < iterations - 1;
-            next_it(&mut dilated, &mut offsets, &mut last_indices, save_next_indices, false, true);
+        // The voxels affected by a modified voxel are found with the reflected kernel
+        let mut offsets = Offsets::new(data, kernel.view(), origin, !is_dilate);
+        while !last_indices.is_empty() && (until_stable || it < iterations) {
+            let save_next_indices = until_stable || it < iterations - 1;
+            next_it(
+                &mut out,
+                &mut offsets,
+                &mut last_indices,
+                allowed,
+                save_next_indices,
+                !is_dilate,
+                is_dilate,
+            );
+            it += 1;
         }
     }
-    dilated
+    // In the "until stable" mode, the last iteration didn't modify anything
+    (out, if until_stable { it - 1 } else { it })
 }
 
-/// Binary opening of a 3D binary image.
+/// Binary opening of a N-D binary image.
 ///
 /// The opening of an input image by a structuring element is the dilation of the erosion of the
 /// image by the structuring element.
@@ -86,17 +182,21 @@ pub fn binary_dilation(
 /// * `kernel` - Structuring element used for the opening.
 /// * `iterations` - The erosion step of the opening, then the dilation step are each repeated
 ///   iterations times.
-pub fn binary_opening(
-    mask: &ArrayRef3<bool>,
-    kernel: &ArrayRef3<bool>,
+///
+/// **Panics** if `iterations` is 0.
+pub fn binary_opening<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
     iterations: usize,
-) -> Mask
+) -> Array<bool, D>
+where
+    D: Dimension,
 {
-    let eroded = binary_erosion(mask, kernel, iterations);
-    binary_dilation(&eroded, kernel, iterations)
+    let eroded = binary_erosion(mask, kernel, iterations, None, true, &[0]);
+    binary_dilation(&eroded, kernel, iterations, None, false, &[0])
 }
 
-/// Binary closing of a 3D binary image.
+/// Binary closing of a N-D binary image.
 ///
 /// The closing of an input image by a structuring element is the erosion of the dilation of the
 /// image by the structuring element.
@@ -109,41 +209,184 @@ pub fn binary_opening(
 /// * `kernel` - Structuring element used for the closing.
 /// * `iterations` - The dilation step of the closing, then the erosion step are each repeated
 ///   iterations times.
-pub fn binary_closing(
-    mask: &ArrayRef3<bool>,
-    kernel: &ArrayRef3<bool>,
+///
+/// **Panics** if `iterations` is 0.
+pub fn binary_closing<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
     iterations: usize,
-) -> Mask
+) -> Array<bool, D>
+where
+    D: Dimension,
 {
-    let dilated = binary_dilation(mask, kernel, iterations);
-    binary_erosion(&dilated, kernel, iterations)
+    let dilated = binary_dilation(mask, kernel, iterations, None, false, &[0]);
+    binary_erosion(&dilated, kernel, iterations, None, true, &[0])
+}
+
+/// Binary white top-hat of a N-D binary image.
+///
+/// The white top-hat is the set of voxels removed by the opening, that is, the objects and
+/// protrusions smaller than the structuring element.
+///
+/// * `mask` - Input binary image.
+/// * `kernel` - Structuring element used for the opening.
+/// * `iterations` - The erosion step of the opening, then the dilation step are each repeated
+///   iterations times.
+///
+/// **Panics** if `iterations` is 0.
+pub fn binary_white_tophat<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    iterations: usize,
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    let mut tophat = binary_opening(mask, kernel, iterations);
+    Zip::from(&mut tophat).and(mask).for_each(|t, &m| *t = m && !*t);
+    tophat
+}
+
+/// Binary black top-hat of a N-D binary image.
+///
+/// The black top-hat is the set of voxels added by the closing, that is, the holes and gaps
+/// smaller than the structuring element.
+///
+/// * `mask` - Input binary image.
+/// * `kernel` - Structuring element used for the closing.
+/// * `iterations` - The dilation step of the closing, then the erosion step are each repeated
+///   iterations times.
+///
+/// **Panics** if `iterations` is 0.
+pub fn binary_black_tophat<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    iterations: usize,
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    let mut tophat = binary_closing(mask, kernel, iterations);
+    Zip::from(&mut tophat).and(mask).for_each(|t, &m| *t = *t && !m);
+    tophat
+}
+
+/// Binary morphological gradient of a N-D binary image.
+///
+/// The gradient is the set of voxels added by the dilation or removed by the erosion, that is,
+/// a band around the boundaries of the objects.
+///
+/// * `mask` - Input binary image.
+/// * `kernel` - Structuring element used for the dilation and the erosion.
+pub fn binary_morphological_gradient<D>(
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    let mut gradient = binary_dilation(mask, kernel, 1, None, false, &[0]);
+    let eroded = binary_erosion(mask, kernel, 1, None, true, &[0]);
+    Zip::from(&mut gradient).and(&eroded).for_each(|g, &e| *g = *g && !e);
+    gradient
+}
+
+/// Binary hit-or-miss transform of a N-D binary image.
+///
+/// A voxel is `true` in the output when `structure1` fits in the objects and `structure2` fits in
+/// the background, i.e. when it matches the pattern defined by both structures. The voxels outside
+/// of the image are considered `false` for both structures, like SciPy.
+///
+/// * `mask` - Binary image in which the pattern is searched.
+/// * `structure1` - Part of the pattern that must be `true`.
+/// * `structure2` - Part of the pattern that must be `false`. The complement of `structure1` is
+///   used when `None`.
+/// * `origin1` - Placement of `structure1` on the voxels, on each axis. A single value can be given
+///   for all axes. `0` centers the structure at `len / 2`, positive values shift it to the left.
+/// * `origin2` - Placement of `structure2` on the voxels. `origin1` is used when `None`.
+///
+/// **Panics** if the origins move the centers outside of the structures.
+pub fn binary_hit_or_miss<D>(
+    mask: &ArrayRef<bool, D>,
+    structure1: &ArrayRef<bool, D>,
+    structure2: Option<&ArrayRef<bool, D>>,
+    origin1: &[isize],
+    origin2: Option<&[isize]>,
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    let data = mask
+        .as_slice_memory_order()
+        .expect("Morphological operations can only be called on arrays with contiguous memory.");
+    let structure2 = match structure2 {
+        Some(structure2) => structure2.to_owned(),
+        None => structure1.mapv(|s| !s),
+    };
+    let origin2 = origin2.unwrap_or(origin1);
+    let mut hits = Offsets::new(mask, structure1.view(), origin1, false);
+    let mut misses = Offsets::new(mask, structure2.view(), origin2, false);
+    // `Offsets` doesn't include the center, so it must be checked separately
+    let center1 = structure1.view().into_dyn()[IxDyn(&hits.centers)];
+    let center2 = structure2.view().into_dyn()[IxDyn(&misses.centers)];
+    let ooi_offset = data.len() as isize;
+
+    let mut out = mask.to_owned();
+    let out_slice = out.as_slice_memory_order_mut().unwrap();
+    for (i, (&m, o)) in data.iter().zip(out_slice).enumerate() {
+        let fits = |offsets: &Offsets, value: bool| {
+            offsets.range().iter().all(|&offset| {
+                offset != ooi_offset && data[(i as isize + offset) as usize] == value
+            })
+        };
+        *o = (!center1 || m) && (!center2 || !m) && fits(&hits, true) && fits(&misses, false);
+        hits.next();
+        misses.next();
+    }
+    out
+}
+
+/// Returns a copy of `mask` with the same memory layout as `data`, so that their slices can be
+/// iterated together.
+fn same_layout<D>(data: &ArrayRef<bool, D>, mask: &ArrayRef<bool, D>) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    assert_eq!(mask.shape(), data.shape(), "`mask` must have the same shape as `data`");
+    let mut allowed = data.to_owned();
+    allowed.assign(mask);
+    allowed
 }
 
 /// Actual erosion work.
 ///
 /// `out` MUST be a clone of `mask`, otherwise this won't work. We're not setting any values
 /// uselessly, to be as fast as possible.
-fn erode(
-    mask: ArrayView3<bool>,
-    out: &mut ArrayViewMut3<bool>,
+fn erode<D>(
+    mask: &ArrayRef<bool, D>,
+    out: &mut Array<bool, D>,
     offsets: &mut Offsets,
+    allowed: Option<&[bool]>,
+    border_value: bool,
     last_indices: &mut Option<Vec<isize>>,
-) {
+) where
+    D: Dimension,
+{
     let mask = mask.as_slice_memory_order().unwrap();
     let out = out.as_slice_memory_order_mut().unwrap();
     let ooi_offset = mask.len() as isize;
 
     let mut i = 0;
     for (&m, o) in mask.iter().zip(out) {
-        if m {
+        if m && allowed.is_none_or(|a| a[i as usize]) {
             for &offset in offsets.range() {
                 // Is offset the special value "Out Of Image"?
-                if offset == ooi_offset {
+                if offset == ooi_offset && border_value {
                     // The offsets are sorted so we can quit as soon as we see the `ooi_offset`
                     break;
                 } else {
                     // unsafe { !*mask.get_unchecked((i + offset) as usize) }
-                    if !mask[(i + offset) as usize] {
+                    if offset == ooi_offset || !mask[(i + offset) as usize] {
                         *o = false;
                         // If we have more than one iteration, note all modified indices
                         if let Some(last_indices) = last_indices {
@@ -165,12 +408,16 @@ fn erode(
 ///
 /// `out` MUST be a clone of `mask`, otherwise this won't work. We're not setting any values
 /// uselessly, to be as fast as possible.
-fn dilate(
-    mask: ArrayView3<bool>,
-    out: &mut Array3<bool>,
+fn dilate<D>(
+    mask: &ArrayRef<bool, D>,
+    out: &mut Array<bool, D>,
     offsets: &mut Offsets,
+    allowed: Option<&[bool]>,
+    border_value: bool,
     last_indices: &mut Option<Vec<isize>>,
-) {
+) where
+    D: Dimension,
+{
     // Even if `erode` and `dilate` could share the same code (as SciPy does), it produces much
     // slower code in practice. See previous function for some documentation.
     let mask = mask.as_slice_memory_order().unwrap();
@@ -179,13 +426,13 @@ fn dilate(
 
     let mut i = 0;
     for (&m, o) in mask.iter().zip(out) {
-        if !m {
+        if !m && allowed.is_none_or(|a| a[i as usize]) {
             for &offset in offsets.range() {
-                if offset == ooi_offset {
+                if offset == ooi_offset && !border_value {
                     break;
                 } else {
                     // unsafe { *mask.get_unchecked((i + offset) as usize) }
-                    if mask[(i + offset) as usize] {
+                    if offset == ooi_offset || mask[(i + offset) as usize] {
                         *o = true;
                         if let Some(last_indices) = last_indices {
                             last_indices.push(i);
@@ -206,14 +453,17 @@ fn dilate(
 ///
 /// - Use `false` and `true` for dilate.
 /// - Use `true` and `false` for erode.
-fn next_it(
-    out: &mut Array3<bool>,
+fn next_it<D>(
+    out: &mut Array<bool, D>,
     offsets: &mut Offsets,
     last_indices: &mut Vec<isize>,
+    allowed: Option<&[bool]>,
     save_next_indices: bool,
     b1: bool,
     b2: bool,
-) {
+) where
+    D: Dimension,
+{
     let out = out.as_slice_memory_order_mut().unwrap();
     let ooi_offset = out.len() as isize;
 
@@ -225,7 +475,7 @@ fn next_it(
         for &offset in offsets.range() {
             if offset == ooi_offset {
                 break;
-            } else {
+            } else if allowed.is_none_or(|a| a[(i + offset) as usize]) {
                 let out = &mut out[(i + offset) as usize];
                 if save_next_indices && *out == b1 {
                     // This time, `i` is the center and `i + offset` is the neighbor
@@ -1,11 +1,11 @@
-use ndarray::{ArrayRef3, ArrayView3};
+use ndarray::{ArrayRef, ArrayView, Dimension, IxDyn};
 
 pub struct Offsets {
     mask_strides: Vec<isize>,
     dim_m1: Vec<usize>,
     offsets: Vec<isize>,
-    axes: [usize; 3],
-    axes_rev: [usize; 3],
+    axes: Vec<usize>,
+    axes_rev: Vec<usize>,
 
     strides: Vec<usize>,
     backstrides: Vec<usize>,
@@ -13,33 +13,71 @@ pub struct Offsets {
     n: usize,
 
     pub coordinates: Vec<usize>,
+    pub centers: Vec<usize>,
     at: usize,
 }
 
 impl Offsets {
-    pub fn new<A>(mask: &ArrayRef3<A>, kernel: ArrayView3<bool>, is_dilate: bool) -> Offsets
+    pub fn new<A, D>(
+        mask: &ArrayRef<A, D>,
+        kernel: ArrayView<bool, D>,
+        origin: &[isize],
+        is_dilate: bool,
+    ) -> Offsets
+    where
+        D: Dimension,
     {
         let mask_shape = mask.shape();
-        let mask_strides = mask.strides().to_vec();
-        let (axes, axes_rev) = if mask_strides[0] > mask_strides[2] {
-            ([2, 1, 0], [0, 1, 2])
-        } else {
-            ([0, 1, 2], [2, 1, 0])
+        let kernel_shape = kernel.shape();
+        let origin = match origin.len() {
+            1 => vec![origin[0]; mask.ndim()],
+            l if l == mask.ndim() => origin.to_vec(),
+            _ => panic!("Inconsistant number of dimensions and origins"),
         };
-        let (offsets, n) = build_offsets(mask_shape, &mask_strides, kernel.view(), is_dilate);
+        // The center of the kernel is at `len / 2 + origin`. The neighbors are between `before`
+        // voxels before the current voxel and `after` voxels after it.
+        let centers: Vec<_> = kernel_shape
+            .iter()
+            .zip(&origin)
+            .map(|(&len, &o)| {
+                let center = (len / 2) as isize + o;
+                assert!((0..len as isize).contains(&center), "`origin` must be inside the kernel");
+                center as usize
+            })
+            .collect();
+        let extents: Vec<_> = kernel_shape
+            .iter()
+            .zip(&centers)
+            .map(|(&len, &c)| if is_dilate { (len - 1 - c, c) } else { (c, len - 1 - c) })
+            .collect();
+        let mask_strides = mask.strides().to_vec();
+        // Walk the axes in memory order, from the fastest to the slowest. The axes of length 1
+        // don't matter, so they are put first.
+        let mut axes: Vec<_> = (0..mask.ndim()).collect();
+        axes.sort_by_key(|&d| if mask_shape[d] > 1 { mask_strides[d] } else { 0 });
+        let axes_rev = axes.iter().rev().cloned().collect();
+        let (offsets, n) =
+            build_offsets(mask_shape, &mask_strides, kernel.view(), &centers, &extents, is_dilate);
         let dim_m1: Vec<_> = mask_shape.iter().map(|&len| len - 1).collect();
 
-        let kernel_shape = kernel.shape();
         let mut strides = vec![0; mask.ndim()];
         strides[mask.ndim() - 1] = n;
         for d in (0..mask.ndim() - 1).rev() {
-            strides[d] = strides[d + 1] * kernel_shape[d];
+            strides[d] = strides[d + 1] * kernel_shape[d + 1];
         }
-        let backstrides = strides.iter().zip(kernel_shape).map(|(&s, &l)| (l - 1) * s).collect();
+        // The last position of an axis, short or not, is at `min(len, kernel_len) - 1`
+        let backstrides = (0..mask.ndim())
+            .map(|d| (kernel_shape[d].min(mask_shape[d]) - 1) * strides[d])
+            .collect();
         let bounds = (0..mask.ndim())
             .map(|d| {
-                let radius = (kernel_shape[d] - 1) / 2;
-                radius..dim_m1[d] - radius
+                let (before, after) = extents[d];
+                if mask_shape[d] > before + after {
+                    before..dim_m1[d] - after
+                } else {
+                    // The axis is too short to have an interior, all its voxels are borders
+                    mask_shape[d]..mask_shape[d]
+                }
             })
             .collect();
 
@@ -54,6 +92,7 @@ impl Offsets {
             bounds,
             n,
             coordinates: vec![0; mask.ndim()],
+            centers,
             at: 0,
         }
     }
@@ -104,44 +143,57 @@ impl Offsets {
 }
 
 /// Builds the kernel offsets.
-fn build_offsets(
+fn build_offsets<D>(
     shape: &[usize],
     strides: &[isize],
-    kernel: ArrayView3<bool>,
+    kernel: ArrayView<bool, D>,
+    centers: &[usize],
+    extents: &[(usize, usize)],
     is_dilate: bool,
-) -> (Vec<isize>, usize) {
-    let radii: Vec<_> = kernel.shape().iter().map(|&len| (len - 1) / 2).collect();
-    let indices = build_indices(kernel, &radii, is_dilate);
+) -> (Vec<isize>, usize)
+where
+    D: Dimension,
+{
+    let indices = build_indices(kernel.view(), centers, is_dilate);
+    if indices.is_empty() {
+        // Only the center, or nothing, is `true`. There are no neighbors to walk.
+        return (vec![], 0);
+    }
 
-    let shape = [shape[0] as isize, shape[1] as isize, shape[2] as isize];
-    let ooi_offset = shape.iter().fold(1, |acc, &s| acc * s);
+    let shape: Vec<_> = shape.iter().map(|&s| s as isize).collect();
+    let ooi_offset = shape.iter().product();
     let build_pos = |d: usize| {
         let mut pos = Vec::with_capacity(kernel.shape()[d]);
-        let radius = radii[d] as isize;
-        pos.extend(0..radius);
-        pos.push(shape[d] / 2);
-        pos.extend(shape[d] - radius..shape[d]);
+        let (before, after) = (extents[d].0 as isize, extents[d].1 as isize);
+        if shape[d] > before + after {
+            // `before` is the first voxel of the interior, all its neighbors are inside the image
+            pos.extend(0..=before);
+            pos.extend(shape[d] - after..shape[d]);
+        } else {
+            // Same layout as the `bounds` of a short axis, the position of a voxel is its index
+            pos.extend(0..before + after + 1);
+        }
         pos
     };
-    let z_pos = build_pos(0);
-    let y_pos = build_pos(1);
-    let x_pos = build_pos(2);
+    let positions: Vec<_> = (0..shape.len()).map(build_pos).collect();
 
+    // All combinations of positions, in logical order
     let mut offsets = vec![];
-    for &z in &z_pos {
-        for &y in &y_pos {
-            for &x in &x_pos {
-                for idx2 in &indices {
-                    let idx = [z + idx2[0], y + idx2[1], x + idx2[2]];
-                    let offset = if idx.iter().zip(shape).any(|(i, s)| !(0..s).contains(i)) {
-                        // This voxel in the current kernel is out of image
-                        ooi_offset
-                    } else {
-                        idx2.iter().zip(strides).fold(0, |acc, (i, s)| acc + i * s)
-                    };
-                    offsets.push(offset)
-                }
-            }
+    let mut pos = vec![0; shape.len()];
+    for pos_idx in ndarray::indices(IxDyn(kernel.shape())) {
+        for (d, p) in pos.iter_mut().enumerate() {
+            *p = positions[d][pos_idx[d]];
+        }
+        for idx2 in &indices {
+            let is_out =
+                pos.iter().zip(idx2).zip(&shape).any(|((p, i), &s)| !(0..s).contains(&(p + i)));
+            let offset = if is_out {
+                // This voxel in the current kernel is out of image
+                ooi_offset
+            } else {
+                idx2.iter().zip(strides).fold(0, |acc, (i, s)| acc + i * s)
+            };
+            offsets.push(offset)
         }
     }
 
@@ -155,24 +207,31 @@ fn build_offsets(
     (offsets, indices.len())
 }
 
-fn build_indices(kernel: ArrayView3<bool>, radii: &[usize], is_dilate: bool) -> Vec<[isize; 3]> {
-    let radii = [radii[0] as isize, radii[1] as isize, radii[2] as isize];
+fn build_indices<D>(
+    kernel: ArrayView<bool, D>,
+    centers: &[usize],
+    is_dilate: bool,
+) -> Vec<Vec<isize>>
+where
+    D: Dimension,
+{
     kernel
+        .into_dyn()
         .indexed_iter()
         .filter_map(|(idx, &b)| {
             if !b {
                 return None;
             }
 
-            // Do not add index (0, 0, 0) because it represents offset 0 which it's useless for
+            // Do not add the center index because it represents offset 0 which it's useless for
             // both `dilate` and `erode`, thanks to the `center_is_true` condition.
-            let centered =
-                [idx.0 as isize - radii[0], idx.1 as isize - radii[1], idx.2 as isize - radii[2]];
-            (centered != [0, 0, 0]).then_some(if is_dilate {
+            let centered: Vec<_> =
+                centers.iter().enumerate().map(|(d, &c)| idx[d] as isize - c as isize).collect();
+            (centered.iter().any(|&c| c != 0)).then_some(if is_dilate {
                 // dilate works by applying offsets on all voxels (checking the state of the
                 // neighbors), not by applying the kernel on all voxels. This frame of reference
                 // switch implies that we must reverse the indices.
-                [-1 * centered[0], -1 * centered[1], -1 * centered[2]]
+                centered.iter().map(|&c| -c).collect()
             } else {
                 // erosion doesn't work "normally" so we don't need to reverse anything
                 centered
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+use ndarray::{Array, ArrayRef, Dimension, IxDyn, Zip};
+
+use crate::binary_dilation_until_stable;
+
+/// Method used by [`reconstruction`](crate::reconstruction).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReconstructionMethod {
+    /// Reconstruction by dilation. The seed is dilated under the mask. The seed must be lower than
+    /// or equal to the mask.
+    Dilation,
+    /// Reconstruction by erosion. The seed is eroded above the mask. The seed must be greater than
+    /// or equal to the mask.
+    Erosion,
+}
+
+/// Binary propagation of a seed inside a mask.
+///
+/// The seed is dilated until it fills all the regions of `mask` that it touches. This is a binary
+/// reconstruction by dilation. The voxels of `seed` outside of `mask` are kept.
+///
+/// * `seed` - Binary image of the markers to propagate.
+/// * `mask` - Binary image defining where the propagation can go.
+/// * `kernel` - Structuring element defining the connectivity of the propagation.
+/// * `border_value` - Value of the voxels outside of the image. `true` also propagates from the
+///   border of the image.
+///
+/// **Panics** if `mask` doesn't have the same shape as `seed`.
+pub fn binary_propagation<D>(
+    seed: &ArrayRef<bool, D>,
+    mask: &ArrayRef<bool, D>,
+    kernel: &ArrayRef<bool, D>,
+    border_value: bool,
+) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    // `binary_dilation_until_stable` needs contiguous memory
+    let seed = seed.as_standard_layout();
+    binary_dilation_until_stable(&seed, kernel, Some(mask), border_value, &[0]).0
+}
+
+/// Greyscale morphological reconstruction.
+///
+/// The reconstruction by dilation repeatedly dilates `seed`, limited by `mask`, until it doesn't
+/// change anymore. The reconstruction by erosion is the dual operation. It's used to compute the
+/// h-maxima, the openings and closings by reconstruction, to fill holes, etc.
+///
+/// This is the hybrid algorithm of L. Vincent (1993), with a raster scan, an anti-raster scan,
+/// then a queue-based propagation.
+///
+/// * `seed` - The image to reconstruct.
+/// * `mask` - The image limiting the reconstruction.
+/// * `footprint` - Structuring element defining the connectivity. Must be of odd length.
+/// * `method` - See the [`ReconstructionMethod`](crate::ReconstructionMethod) enum for more
+///   information.
+///
+/// **Panics** if `mask` doesn't have the same shape as `seed`, if `footprint` has an even length
+/// or if `seed` isn't lower (dilation) or greater (erosion) than or equal to `mask`.
+pub fn reconstruction<A, D>(
+    seed: &ArrayRef<A, D>,
+    mask: &ArrayRef<A, D>,
+    footprint: &ArrayRef<bool, D>,
+    method: ReconstructionMethod,
+) -> Array<A, D>
+where
+    A: Copy + PartialOrd,
+    D: Dimension,
+{
+    assert_eq!(seed.shape(), mask.shape(), "`mask` must have the same shape as `seed`");
+    assert!(footprint.shape().iter().all(|&len| len % 2 == 1), "`footprint` must have odd lengths");
+    let is_dilation = method == ReconstructionMethod::Dilation;
+    assert!(
+        Zip::from(seed).and(mask).all(|&s, &m| if is_dilation { s <= m } else { s >= m }),
+        "`seed` must be lower (dilation) or greater (erosion) than or equal to `mask`"
+    );
+    if seed.is_empty() {
+        return seed.to_owned();
+    }
+    // `better(a, b)` is `a > b` for the dilation and `a < b` for the erosion
+    let better = |a: A, b: A| if is_dilation { a > b } else { a < b };
+    let worst = |a: A, b: A| if better(a, b) { b } else { a };
+
+    // The images are padded with a value that can't propagate
+    let sentinel = seed.iter().fold(seed.first().cloned().unwrap(), |acc, &s| worst(acc, s));
+    let radii: Vec<_> = footprint.shape().iter().map(|&len| len / 2).collect();
+    let padded_shape: Vec<_> = seed.shape().iter().zip(&radii).map(|(&s, &r)| s + 2 * r).collect();
+    let mut strides = vec![1; seed.ndim()];
+    for d in (0..seed.ndim().saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * padded_shape[d + 1];
+    }
+    let interior: Vec<_> = ndarray::indices(IxDyn(seed.shape()))
+        .into_iter()
+        .map(|idx| (0..seed.ndim()).fold(0, |acc, d| acc + (idx[d] + radii[d]) * strides[d]))
+        .collect();
+    let mut marker = vec![sentinel; padded_shape.iter().product()];
+    let mut limit = marker.clone();
+    for ((&p, &s), &m) in interior.iter().zip(seed.iter()).zip(mask.iter()) {
+        marker[p] = s;
+        limit[p] = m;
+    }
+
+    // The neighbors scanned before (`backward`) and after (`forward`) a voxel in raster order
+    let footprint = footprint.view().into_dyn();
+    let (mut backward, mut forward) = (vec![], vec![]);
+    for (idx, &f) in footprint.indexed_iter() {
+        let offset = (0..seed.ndim())
+            .fold(0, |acc, d| acc + (idx[d] as isize - radii[d] as isize) * strides[d] as isize);
+        if f && offset < 0 {
+            backward.push(offset);
+        } else if f && offset > 0 {
+            forward.push(offset);
+        }
+    }
+    let propagate = |marker: &[A], limit: &[A], p: usize, neighbors: &[isize]| {
+        let best = neighbors.iter().fold(marker[p], |acc, &o| {
+            let v = marker[(p as isize + o) as usize];
+            if better(v, acc) {
+                v
+            } else {
+                acc
+            }
+        });
+        worst(best, limit[p])
+    };
+
+    // Raster scan
+    for &p in &interior {
+        marker[p] = propagate(&marker, &limit, p, &backward);
+    }
+
+    // Anti-raster scan. The voxels that can still propagate to a neighbor are queued.
+    let mut queue = VecDeque::new();
+    for &p in interior.iter().rev() {
+        marker[p] = propagate(&marker, &limit, p, &forward);
+        let can_propagate = forward.iter().any(|&o| {
+            let q = (p as isize + o) as usize;
+            better(marker[p], marker[q]) && better(limit[q], marker[q])
+        });
+        if can_propagate {
+            queue.push_back(p);
+        }
+    }
+
+    // Queue-based propagation
+    let neighbors: Vec<_> = backward.iter().chain(&forward).cloned().collect();
+    while let Some(p) = queue.pop_front() {
+        for &o in &neighbors {
+            let q = (p as isize + o) as usize;
+            if better(marker[p], marker[q]) && limit[q] != marker[q] {
+                marker[q] = worst(marker[p], limit[q]);
+                queue.push_back(q);
+            }
+        }
+    }
+
+    let reconstructed = interior.iter().map(|&p| marker[p]).collect();
+    Array::from_shape_vec(seed.raw_dim(), reconstructed).unwrap()
+}
@@ -0,0 +1,375 @@
+use ndarray::{Array, Array2, Array3, ArrayRef, ArrayRef2, ArrayRef3, Dimension, Slice, Zip};
+
+use crate::distance_transform_edt;
+
+/// A branch of a skeleton, found by [`skeleton_graph`](crate::skeleton_graph).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonBranch {
+    /// Coordinates of the voxels of the branch, in order. The first and last voxels are endpoints
+    /// or branch points. They are the same voxel if the branch is a cycle.
+    pub voxels: Vec<Vec<usize>>,
+    /// Length of the branch, in voxels. The diagonal steps count as `sqrt(2)` or `sqrt(3)`.
+    pub length: f64,
+}
+
+/// Graph of a skeleton, computed by [`skeleton_graph`](crate::skeleton_graph).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonGraph {
+    /// Coordinates of the voxels with more than 2 neighbors.
+    pub branch_points: Vec<Vec<usize>>,
+    /// Coordinates of the voxels with a single neighbor.
+    pub endpoints: Vec<Vec<usize>>,
+    /// The branches joining the endpoints and the branch points.
+    pub branches: Vec<SkeletonBranch>,
+}
+
+/// Skeletonize a 3D binary image by topology-preserving thinning.
+///
+/// This is the algorithm of T. C. Lee, R. L. Kashyap and C. N. Chu (1994). The objects are thinned
+/// from their 6 sides in turn. A voxel is removed if it's not the end of a line and if its removal
+/// doesn't change the topology, i.e. the number of objects, cavities and tunnels. The objects
+/// are 26-connected and the background is 6-connected. It's mostly used to extract the
+/// centerlines of tubular structures.
+///
+/// * `mask` - Binary image to skeletonize.
+pub fn skeletonize_3d(mask: &ArrayRef3<bool>) -> Array3<bool> {
+    let (mut padded, strides) = pad_false(mask);
+    let topology = Topology::new(&strides);
+    let data = padded.as_slice_mut().unwrap();
+    let mut voxels: Vec<_> = (0..data.len()).filter(|&p| data[p]).collect();
+
+    loop {
+        let mut changed = false;
+        for &direction in &topology.offsets_face[..] {
+            // All the simple border points of this side are found before removing them. They are
+            // then removed one by one, if they are still simple.
+            let candidates: Vec<_> = voxels
+                .iter()
+                .cloned()
+                .filter(|&p| {
+                    !data[(p as isize + direction) as usize]
+                        && topology.nb_neighbors(data, p) > 1
+                        && topology.is_simple(data, p)
+                })
+                .collect();
+            for p in candidates {
+                data[p] = false;
+                if topology.is_simple(data, p) {
+                    changed = true;
+                } else {
+                    data[p] = true;
+                }
+            }
+            voxels.retain(|&p| data[p]);
+        }
+        if !changed {
+            break;
+        }
+    }
+    unpad(&padded)
+}
+
+/// Medial axis of a 2D binary image.
+///
+/// The pixels are removed in the order of their distance to the background, from the closest to
+/// the farthest, as long as their removal doesn't change the topology and they are not the end of
+/// a line. The objects are 8-connected and the background is 4-connected.
+///
+/// Returns the medial axis and the Euclidean distance to the background of its pixels. The
+/// distance is `0` outside of the medial axis.
+///
+/// * `mask` - Binary image whose medial axis is computed.
+pub fn medial_axis(mask: &ArrayRef2<bool>) -> (Array2<bool>, Array2<f64>) {
+    let (mut distance, _) = distance_transform_edt(mask, &[1.0], false);
+    let (mut padded, strides) = pad_false(mask);
+    let topology = Topology::new(&strides);
+    let data = padded.as_slice_mut().unwrap();
+
+    // The pixels with the same distance are ordered by the number of background pixels around
+    // them, so that the corners are removed last. `data` and `distance.iter()` are both in the
+    // logical order.
+    let mut order: Vec<_> = (0..data.len())
+        .filter(|&p| data[p])
+        .zip(distance.iter().filter(|&&d| d > 0.0))
+        .map(|(p, &d)| (p, d, topology.offsets.len() - 1 - topology.nb_neighbors(data, p)))
+        .collect();
+    order.sort_by(|(_, d1, c1), (_, d2, c2)| d1.partial_cmp(d2).unwrap().then(c1.cmp(c2)));
+    let order: Vec<_> = order.into_iter().map(|(p, _, _)| p).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &p in &order {
+            if data[p] && topology.nb_neighbors(data, p) > 1 && topology.is_simple(data, p) {
+                data[p] = false;
+                changed = true;
+            }
+        }
+    }
+    let skeleton = unpad(&padded);
+    Zip::from(&mut distance).and(&skeleton).for_each(|d, &s| {
+        if !s {
+            *d = 0.0;
+        }
+    });
+    (skeleton, distance)
+}
+
+/// Analyzes the graph of a N-D skeleton.
+///
+/// The voxels are connected to all their neighbors, including the diagonal ones, unless a diagonal
+/// neighbor is also connected through a common neighbor. The voxels with a single neighbor are the
+/// endpoints and the voxels with more than 2 neighbors are the branch points. The branches are the
+/// paths of voxels joining them. A cycle without any branch point is a single branch. The isolated
+/// voxels are ignored.
+///
+/// * `skeleton` - Binary image of a skeleton, like the output of
+///   [`skeletonize_3d`](crate::skeletonize_3d) or [`medial_axis`](crate::medial_axis).
+pub fn skeleton_graph<D>(skeleton: &ArrayRef<bool, D>) -> SkeletonGraph
+where
+    D: Dimension,
+{
+    let (padded, strides) = pad_false(skeleton);
+    let data = padded.as_slice().unwrap();
+    // A link to a neighbor is ignored if the neighbor can also be reached with smaller steps, by
+    // passing through a voxel that moves on a part of the axes only. This avoids the spurious
+    // branch points around the junctions.
+    let neighborhood = neighborhood(&strides);
+    let is_shortcut = |moves: &[isize], other: &[isize]| {
+        other != moves
+            && other.iter().any(|&o| o != 0)
+            && other.iter().zip(moves).all(|(&o, &m)| o == 0 || o == m)
+    };
+    let (links, steps): (Vec<_>, Vec<_>) = neighborhood
+        .iter()
+        .filter(|(_, moves)| moves.iter().any(|&m| m != 0))
+        .map(|(offset, moves)| {
+            let shortcuts: Vec<_> = neighborhood
+                .iter()
+                .filter(|(_, other)| is_shortcut(moves, other))
+                .map(|&(o, _)| o)
+                .collect();
+            let nb_moves = moves.iter().filter(|&&m| m != 0).count();
+            ((*offset, shortcuts), (nb_moves as f64).sqrt())
+        })
+        .unzip();
+    let at = |p: usize, o: isize| (p as isize + o) as usize;
+    let neighbors = |p: usize| {
+        links
+            .iter()
+            .filter(move |(o, shortcuts)| {
+                data[at(p, *o)] && shortcuts.iter().all(|&s| !data[at(p, s)])
+            })
+            .map(move |&(o, _)| at(p, o))
+    };
+    let degrees: Vec<_> =
+        (0..data.len()).map(|p| if data[p] { neighbors(p).count() } else { 0 }).collect();
+    let is_node = |p: usize| degrees[p] == 1 || degrees[p] > 2;
+
+    let mut paths = vec![];
+    let mut visited = vec![false; data.len()];
+    for p in (0..data.len()).filter(|&p| data[p] && is_node(p)) {
+        for q in neighbors(p) {
+            if is_node(q) {
+                // Two adjacent branch points are part of the same junction
+                if p < q && (degrees[p] == 1 || degrees[q] == 1) {
+                    paths.push(vec![p, q]);
+                }
+            } else if !visited[q] {
+                paths.push(trace(vec![p, q], &degrees, &mut visited, neighbors));
+            }
+        }
+    }
+    // Only the cycles without any node are left
+    for p in 0..data.len() {
+        if degrees[p] == 2 && !visited[p] {
+            visited[p] = true;
+            let q = neighbors(p).next().unwrap();
+            paths.push(trace(vec![p, q], &degrees, &mut visited, neighbors));
+        }
+    }
+
+    let shape = padded.shape();
+    let coordinates = |mut p: usize| {
+        let mut coordinates = vec![0; shape.len()];
+        for d in (0..shape.len()).rev() {
+            coordinates[d] = p % shape[d] - 1;
+            p /= shape[d];
+        }
+        coordinates
+    };
+    let branches = paths
+        .into_iter()
+        .map(|path| {
+            let length = path
+                .windows(2)
+                .map(|w| {
+                    let o = w[1] as isize - w[0] as isize;
+                    steps[links.iter().position(|&(offset, _)| offset == o).unwrap()]
+                })
+                .sum();
+            SkeletonBranch { voxels: path.into_iter().map(coordinates).collect(), length }
+        })
+        .collect();
+    let nodes = |wanted: fn(usize) -> bool| {
+        (0..data.len()).filter(|&p| wanted(degrees[p])).map(coordinates).collect()
+    };
+    SkeletonGraph {
+        branch_points: nodes(|degree| degree > 2),
+        endpoints: nodes(|degree| degree == 1),
+        branches,
+    }
+}
+
+/// Follows the voxels of `path` having 2 neighbors, until a node or the start of the path is
+/// reached.
+fn trace<I>(
+    mut path: Vec<usize>,
+    degrees: &[usize],
+    visited: &mut [bool],
+    neighbors: impl Fn(usize) -> I,
+) -> Vec<usize>
+where
+    I: Iterator<Item = usize>,
+{
+    loop {
+        let (previous, current) = (path[path.len() - 2], path[path.len() - 1]);
+        if degrees[current] != 2 || current == path[0] {
+            return path;
+        }
+        visited[current] = true;
+        path.push(neighbors(current).find(|&n| n != previous).unwrap());
+    }
+}
+
+/// Returns a copy of `mask` with a border of one `false` voxel, in standard layout, and its
+/// strides.
+fn pad_false<D>(mask: &ArrayRef<bool, D>) -> (Array<bool, D>, Vec<usize>)
+where
+    D: Dimension,
+{
+    let mut padded_dim = mask.raw_dim();
+    for len in padded_dim.slice_mut() {
+        *len += 2;
+    }
+    let mut padded = Array::from_elem(padded_dim, false);
+    padded.slice_each_axis_mut(|ad| Slice::from(1..ad.len - 1)).assign(mask);
+    let strides = padded.strides().iter().map(|&s| s as usize).collect();
+    (padded, strides)
+}
+
+/// Removes the border added by `pad_false`.
+fn unpad<D>(padded: &Array<bool, D>) -> Array<bool, D>
+where
+    D: Dimension,
+{
+    padded.slice_each_axis(|ad| Slice::from(1..ad.len - 1)).to_owned()
+}
+
+/// Offsets of the 3^ndim voxels of a neighborhood, in logical order, with their moves on each
+/// axis.
+fn neighborhood(strides: &[usize]) -> Vec<(isize, Vec<isize>)> {
+    ndarray::indices(vec![3; strides.len()])
+        .into_iter()
+        .map(|idx| {
+            let moves: Vec<_> = idx.slice().iter().map(|&i| i as isize - 1).collect();
+            let offset = moves.iter().zip(strides).map(|(&m, &s)| m * s as isize).sum();
+            (offset, moves)
+        })
+        .collect()
+}
+
+/// Decides if a voxel can be removed without changing the topology of a 2D or 3D image.
+///
+/// A voxel is simple if it's adjacent to a single object and a single background component, in its
+/// neighborhood. See G. Bertrand and G. Malandain (1994) for the topological numbers.
+struct Topology {
+    /// Offsets of the neighborhood in the padded image
+    offsets: Vec<isize>,
+    /// Offsets of the neighbors sharing a face with the voxel
+    offsets_face: Vec<isize>,
+    /// For each position of the neighborhood, its neighbors in the neighborhood with full
+    /// connectivity (26 in 3D, 8 in 2D), then with face connectivity (6 in 3D, 4 in 2D)
+    full: Vec<Vec<usize>>,
+    face: Vec<Vec<usize>>,
+    /// The positions that don't move on all 3 axes. Only those are used for the background.
+    near: Vec<bool>,
+    center: usize,
+}
+
+impl Topology {
+    fn new(strides: &[usize]) -> Topology {
+        let (offsets, positions): (Vec<_>, Vec<_>) = neighborhood(strides).into_iter().unzip();
+        let moves = |a: &[isize], b: &[isize]| a.iter().zip(b).filter(|(a, b)| a != b).count();
+        let chebyshev =
+            |a: &[isize], b: &[isize]| a.iter().zip(b).map(|(a, b)| (a - b).abs()).max().unwrap();
+        let adjacent = |i: usize, is_face: bool| -> Vec<usize> {
+            (0..positions.len())
+                .filter(|&j| {
+                    let (a, b) = (&positions[i], &positions[j]);
+                    j != i && chebyshev(a, b) == 1 && (!is_face || moves(a, b) == 1)
+                })
+                .collect()
+        };
+        let center = positions.len() / 2;
+        Topology {
+            offsets_face: adjacent(center, true).into_iter().map(|j| offsets[j]).collect(),
+            offsets,
+            full: (0..positions.len()).map(|i| adjacent(i, false)).collect(),
+            face: (0..positions.len()).map(|i| adjacent(i, true)).collect(),
+            near: positions.iter().map(|p| p.iter().filter(|&&c| c != 0).count() < 3).collect(),
+            center,
+        }
+    }
+
+    fn nb_neighbors(&self, data: &[bool], p: usize) -> usize {
+        self.offsets.iter().filter(|&&o| o != 0 && data[(p as isize + o) as usize]).count()
+    }
+
+    fn is_simple(&self, data: &[bool], p: usize) -> bool {
+        let mut values = [false; 27];
+        for (v, &o) in values.iter_mut().zip(&self.offsets) {
+            *v = data[(p as isize + o) as usize];
+        }
+        let values = &values[..self.offsets.len()];
+
+        // The objects, with full connectivity
+        let objects = |i: usize| i != self.center && values[i];
+        if self.nb_components(objects, &self.full, |_| true) != 1 {
+            return false;
+        }
+        // The background, with face connectivity, touching a face of the voxel
+        let background = |i: usize| i != self.center && !values[i] && self.near[i];
+        let touches_face = |i: usize| self.face[self.center].contains(&i);
+        self.nb_components(background, &self.face, touches_face) == 1
+    }
+
+    /// Number of connected components of the members that contain at least one `seed`.
+    fn nb_components(
+        &self,
+        is_member: impl Fn(usize) -> bool,
+        adjacency: &[Vec<usize>],
+        is_seed: impl Fn(usize) -> bool,
+    ) -> usize {
+        let mut visited = [false; 27];
+        let mut stack = vec![];
+        let mut nb = 0;
+        for start in 0..adjacency.len() {
+            if visited[start] || !is_member(start) || !is_seed(start) {
+                continue;
+            }
+            nb += 1;
+            visited[start] = true;
+            stack.push(start);
+            while let Some(i) = stack.pop() {
+                for &j in &adjacency[i] {
+                    if !visited[j] && is_member(j) {
+                        visited[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        nb
+    }
+}
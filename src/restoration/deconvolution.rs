@@ -0,0 +1,158 @@
+use ndarray::{Array, ArrayRef, Dimension, IxDyn, Slice, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{convolve, correlate, BorderMode};
+
+/// Maximal number of conjugate gradient iterations of the Wiener deconvolution.
+const MAX_CG_ITERATIONS: usize = 500;
+
+/// Wiener-Hunt deconvolution of a N-D image.
+///
+/// The result minimizes `|psf * u - data|² + balance * |Δu|²`, where `*` is the convolution and
+/// `Δ` the discrete Laplacian. The image is considered to be 0 outside its boundaries. As there's
+/// no FFT in this crate, the minimum is found with the conjugate gradient method.
+///
+/// * `data` - The input N-D data.
+/// * `psf` - The point spread function, same number of dimensions as `data`. It should sum to 1.
+/// * `balance` - The regularization weight. A greater weight reduces the noise amplification, at
+///   the expense of sharpness.
+///
+/// **Panics** if `psf` is empty or if `balance` is negative.
+pub fn wiener<A, D>(data: &ArrayRef<A, D>, psf: &ArrayRef<A, D>, balance: A) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    assert!(balance >= A::zero(), "`balance` must be non-negative");
+    let psf = centered_psf(psf);
+    let laplacian = laplacian_kernel(&psf);
+    let zero = BorderMode::Constant(A::zero());
+
+    // Normal equations `(Hᵀ H + balance * Lᵀ L) u = Hᵀ data`, where `H` is the convolution by
+    // `psf` and `L` the Laplacian. With zero padding, `Hᵀ` is the correlation by `psf` and `L` is
+    // symmetric.
+    let operator = |u: &Array<A, D>| {
+        let blurred = convolve(u, &psf, zero, 0);
+        let mut out = correlate(&blurred, &psf, zero, 0);
+        if balance > A::zero() {
+            let l = correlate(u, &laplacian, zero, 0);
+            let ll = correlate(&l, &laplacian, zero, 0);
+            out.zip_mut_with(&ll, |o, &v| *o = *o + balance * v);
+        }
+        out
+    };
+    let dot = |a: &Array<A, D>, b: &Array<A, D>| {
+        Zip::from(a).and(b).fold(A::zero(), |acc, &a, &b| acc + a * b)
+    };
+
+    let rhs = correlate(data, &psf, zero, 0);
+    let mut u = data.to_owned();
+    let mut r = rhs.clone();
+    r.zip_mut_with(&operator(&u), |r, &v| *r = *r - v);
+    let mut p = r.clone();
+    let mut rr = dot(&r, &r);
+    let tolerance = dot(&rhs, &rhs) * A::epsilon();
+    for _ in 0..MAX_CG_ITERATIONS {
+        if rr <= tolerance {
+            break;
+        }
+        let ap = operator(&p);
+        let alpha = rr / dot(&p, &ap);
+        Zip::from(&mut u).and(&p).for_each(|u, &p| *u = *u + alpha * p);
+        Zip::from(&mut r).and(&ap).for_each(|r, &ap| *r = *r - alpha * ap);
+        let new_rr = dot(&r, &r);
+        let beta = new_rr / rr;
+        Zip::from(&mut p).and(&r).for_each(|p, &r| *p = r + beta * *p);
+        rr = new_rr;
+    }
+    u
+}
+
+/// Richardson-Lucy deconvolution of a N-D image.
+///
+/// This iterative maximum likelihood method assumes Poisson noise, which makes it well suited for
+/// microscopy. The estimation starts from a constant image (the mean of `data`) and is multiplied
+/// at each iteration by `psf^T * (data / (psf * estimation))`. The image is extended beyond its
+/// boundaries with the [`BorderMode::Reflect`](crate::BorderMode) mode.
+///
+/// * `data` - The input N-D data. Its values should be positive.
+/// * `psf` - The point spread function, same number of dimensions as `data`. It should sum to 1.
+/// * `iterations` - The number of iterations. More iterations give a sharper, but noisier, result.
+/// * `clip` - If `true`, the values of the result are clipped to `[-1, 1]`, like in scikit-image.
+///
+/// **Panics** if `psf` is empty.
+pub fn richardson_lucy<A, D>(
+    data: &ArrayRef<A, D>,
+    psf: &ArrayRef<A, D>,
+    iterations: usize,
+    clip: bool,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    let psf = centered_psf(psf);
+    let mean = data.sum() / A::from_usize(data.len()).unwrap();
+    let mut estimation = Array::from_elem(data.raw_dim(), mean);
+    for _ in 0..iterations {
+        let mut ratio = convolve(&estimation, &psf, BorderMode::Reflect, 0);
+        Zip::from(&mut ratio).and(data).for_each(|r, &d| {
+            *r = if *r == A::zero() { A::zero() } else { d / *r };
+        });
+        let correction = correlate(&ratio, &psf, BorderMode::Reflect, 0);
+        estimation.zip_mut_with(&correction, |e, &c| *e = *e * c);
+    }
+
+    if clip {
+        estimation.mapv_inplace(|v| v.max(-A::one()).min(A::one()));
+    }
+    estimation
+}
+
+/// Returns `psf` embedded in the center of a cube with odd lengths, as required by `correlate`.
+fn centered_psf<A, D>(psf: &ArrayRef<A, D>) -> Array<A, D>
+where
+    A: Float,
+    D: Dimension,
+{
+    assert!(!psf.is_empty(), "`psf` must not be empty");
+    let len = psf.shape().iter().map(|&l| l | 1).max().unwrap();
+    let mut shape = psf.raw_dim();
+    for d in 0..psf.ndim() {
+        shape[d] = len;
+    }
+    let mut out = Array::zeros(shape);
+    out.slice_each_axis_mut(|ad| {
+        let start = len / 2 - psf.shape()[ad.axis.index()] / 2;
+        Slice::from(start..start + psf.shape()[ad.axis.index()])
+    })
+    .assign(psf);
+    out
+}
+
+/// Returns the discrete Laplacian kernel with the same dimensions as `like`.
+fn laplacian_kernel<A, D>(like: &Array<A, D>) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    let ndim = like.ndim();
+    let mut shape = like.raw_dim();
+    for d in 0..ndim {
+        shape[d] = 3;
+    }
+    let mut kernel = Array::zeros(shape);
+    {
+        let mut kernel = kernel.view_mut().into_dyn();
+        let center = IxDyn(&vec![1; ndim]);
+        kernel[&center] = A::from_usize(2 * ndim).unwrap();
+        for d in 0..ndim {
+            for n in [0, 2] {
+                let mut idx = center.clone();
+                idx[d] = n;
+                kernel[&idx] = -A::one();
+            }
+        }
+    }
+    kernel
+}
@@ -0,0 +1,2 @@
+pub mod deconvolution;
+pub mod total_variation;
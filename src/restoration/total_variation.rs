@@ -0,0 +1,176 @@
+use ndarray::{Array, ArrayRef, Axis, Dimension, Slice, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{array_like, pad, read_spacing, PadMode};
+
+/// Total variation denoising of a N-D image, using Chambolle's projection algorithm.
+///
+/// The result minimizes `|u - data|² / 2 + weight * TV(u)`. Contrary to the Gaussian filter, this
+/// smoothing preserves the edges.
+///
+/// * `data` - The input N-D data.
+/// * `weight` - Denoising weight. A greater weight removes more noise, at the expense of fidelity
+///   to `data`.
+/// * `max_iter` - Maximal number of iterations.
+/// * `eps` - Relative difference of the cost function that determines the stop criterion. The
+///   algorithm stops when `|E_(n-1) - E_n| < eps * E_0`.
+/// * `spacing` - The voxel size along each axis. A single value can be given for all axes.
+///
+/// **Panics** if `weight` is not positive or if `spacing` doesn't have 1 or `data.ndim()` values.
+pub fn denoise_tv<A, D>(
+    data: &ArrayRef<A, D>,
+    weight: A,
+    max_iter: usize,
+    eps: A,
+    spacing: &[A],
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    assert!(weight > A::zero(), "`weight` must be positive");
+    let spacing = read_spacing(data.ndim(), spacing);
+    let ndim = data.ndim();
+    let tau = A::one()
+        / (A::from(2.0).unwrap() * spacing.iter().fold(A::zero(), |acc, &h| acc + (h * h).recip()));
+    let size = A::from_usize(data.len()).unwrap();
+
+    // `p` is the dual variable and `g` holds the gradients of `out` along each axis
+    let mut p: Vec<_> = (0..ndim).map(|_| array_like(data, data.dim(), A::zero())).collect();
+    let mut g = p.clone();
+    let mut d = array_like(data, data.dim(), A::zero());
+    let mut norm = array_like(data, data.dim(), A::zero());
+    let mut out = data.to_owned();
+
+    let mut first_energy = A::zero();
+    let mut last_energy = A::zero();
+    for i in 0..max_iter {
+        if i > 0 {
+            // `d` is the (negative) divergence of `p`
+            d.fill(A::zero());
+            for (ax, (p, &h)) in p.iter().zip(spacing.iter()).enumerate() {
+                let n = p.len_of(Axis(ax));
+                Zip::from(&mut d).and(p).for_each(|d, &p| *d = *d - p / h);
+                Zip::from(d.slice_axis_mut(Axis(ax), Slice::from(1..)))
+                    .and(p.slice_axis(Axis(ax), Slice::from(..n - 1)))
+                    .for_each(|d, &p| *d = *d + p / h);
+            }
+            Zip::from(&mut out).and(data).and(&d).for_each(|o, &v, &d| *o = v + d);
+        }
+        let mut energy = d.fold(A::zero(), |acc, &v| acc + v * v);
+
+        for (ax, (g, &h)) in g.iter_mut().zip(spacing.iter()).enumerate() {
+            let n = out.len_of(Axis(ax));
+            Zip::from(g.slice_axis_mut(Axis(ax), Slice::from(..n - 1)))
+                .and(out.slice_axis(Axis(ax), Slice::from(1..)))
+                .and(out.slice_axis(Axis(ax), Slice::from(..n - 1)))
+                .for_each(|g, &next, &current| *g = (next - current) / h);
+        }
+
+        norm.fill(A::zero());
+        for g in &g {
+            Zip::from(&mut norm).and(g).for_each(|n, &g| *n = *n + g * g);
+        }
+        norm.mapv_inplace(|n| n.sqrt());
+        energy = energy + weight * norm.sum();
+        norm.mapv_inplace(|n| n * tau / weight + A::one());
+        for (p, g) in p.iter_mut().zip(&g) {
+            Zip::from(p).and(g).and(&norm).for_each(|p, &g, &n| *p = (*p - tau * g) / n);
+        }
+
+        energy = energy / size;
+        if i == 0 {
+            first_energy = energy;
+        } else if (last_energy - energy).abs() < eps * first_energy {
+            break;
+        }
+        last_energy = energy;
+    }
+    out
+}
+
+/// Isotropic total variation denoising of a N-D image, using the split Bregman method.
+///
+/// The result minimizes `weight * |u - data|² / 2 + TV(u)`. The border of the image is handled
+/// with the [`PadMode::Reflect`](crate::PadMode) mode.
+///
+/// * `data` - The input N-D data.
+/// * `weight` - Fidelity weight. **Contrary to [`denoise_tv`](crate::denoise_tv)**, a smaller
+///   weight removes more noise, at the expense of fidelity to `data`.
+/// * `max_iter` - Maximal number of iterations.
+/// * `eps` - The algorithm stops when the root mean square difference between two iterations is
+///   lower than `eps`.
+///
+/// **Panics** if `weight` is not positive.
+pub fn denoise_tv_bregman<A, D>(
+    data: &ArrayRef<A, D>,
+    weight: A,
+    max_iter: usize,
+    eps: A,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    assert!(weight > A::zero(), "`weight` must be positive");
+    let ndim = data.ndim();
+    let lambda = weight + weight;
+    let norm = weight + A::from_usize(2 * ndim).unwrap() * lambda;
+    let size = A::from_usize(data.len()).unwrap();
+
+    // All computations are done in a padded image to avoid checking the borders. The padded values
+    // are never updated.
+    let data = data.view().into_dyn();
+    let mut out = pad(&data, &[[1, 1]], PadMode::Reflect);
+    let strides: Vec<_> = out.strides().iter().map(|&s| s as usize).collect();
+    let offsets: Vec<_> = data
+        .indexed_iter()
+        .map(|(idx, _)| (0..ndim).fold(0, |acc, d| acc + (idx[d] + 1) * strides[d]))
+        .collect();
+    let input = out.as_slice_memory_order().unwrap().to_vec();
+
+    let new_buffer = || vec![A::zero(); input.len()];
+    let mut d: Vec<_> = (0..ndim).map(|_| new_buffer()).collect();
+    let mut b: Vec<_> = (0..ndim).map(|_| new_buffer()).collect();
+    let mut gradient = vec![A::zero(); ndim];
+    let u = out.as_slice_memory_order_mut().unwrap();
+
+    for _ in 0..max_iter {
+        let mut rmse = A::zero();
+        for &i in &offsets {
+            let previous = u[i];
+
+            // Gauss-Seidel update
+            let mut sum = A::zero();
+            for (s, (d, b)) in strides.iter().zip(d.iter().zip(&b)) {
+                sum = sum + u[i + s] + u[i - s] + d[i - s] - d[i] - b[i - s] + b[i];
+            }
+            let new = (lambda * sum + weight * input[i]) / norm;
+            u[i] = new;
+            rmse = rmse + (new - previous) * (new - previous);
+
+            // Shrinkage
+            let mut magnitude = A::zero();
+            for ((g, &s), b) in gradient.iter_mut().zip(&strides).zip(&b) {
+                *g = u[i + s] - new;
+                magnitude = magnitude + (*g + b[i]) * (*g + b[i]);
+            }
+            let magnitude = magnitude.sqrt();
+            let shrink = if magnitude > lambda.recip() {
+                (magnitude - lambda.recip()) / magnitude
+            } else {
+                A::zero()
+            };
+            for ((&g, d), b) in gradient.iter().zip(&mut d).zip(&mut b) {
+                d[i] = shrink * (g + b[i]);
+                b[i] = b[i] + g - d[i];
+            }
+        }
+
+        if (rmse / size).sqrt() < eps {
+            break;
+        }
+    }
+
+    out.slice_each_axis(|ad| Slice::from(1..ad.len - 1)).to_owned().into_dimensionality().unwrap()
+}
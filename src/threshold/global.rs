@@ -0,0 +1,391 @@
+use ndarray::{ArrayRef, Dimension, Zip};
+use num_traits::ToPrimitive;
+
+/// Computes a threshold with Otsu's method.
+///
+/// The threshold maximizes the variance between the background and the foreground, which are
+/// respectively `data <= threshold` and `data > threshold`.
+///
+/// * `data` - The input N-D data.
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `nbins` is 0, if `mask` doesn't have the same shape as `data` or if there's no
+/// voxel to use.
+pub fn threshold_otsu<A, D>(
+    data: &ArrayRef<A, D>,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let (hist, centers) = histogram(&values(data, mask), nbins);
+    if centers.len() == 1 {
+        return centers[0];
+    }
+
+    // Weight and mean of the background (`data <= centers[i]`) and of the foreground
+    let nb_bins = hist.len();
+    let mut weight1 = vec![0.0; nb_bins];
+    let mut mean1 = vec![0.0; nb_bins];
+    let (mut w, mut s) = (0.0, 0.0);
+    for i in 0..nb_bins {
+        w += hist[i];
+        s += hist[i] * centers[i];
+        weight1[i] = w;
+        mean1[i] = if w > 0.0 { s / w } else { 0.0 };
+    }
+    let (total_weight, total_sum) = (w, s);
+
+    let mut best = (f64::NEG_INFINITY, 0);
+    for i in 0..nb_bins - 1 {
+        let weight2 = total_weight - weight1[i];
+        if weight1[i] == 0.0 || weight2 == 0.0 {
+            continue;
+        }
+        let mean2 = (total_sum - mean1[i] * weight1[i]) / weight2;
+        let variance = weight1[i] * weight2 * (mean1[i] - mean2).powi(2);
+        if variance > best.0 {
+            best = (variance, i);
+        }
+    }
+    centers[best.1]
+}
+
+/// Computes `classes - 1` thresholds with the multi-Otsu method.
+///
+/// The thresholds maximize the variance between the `classes` classes. The class `c` contains the
+/// values in `(thresholds[c - 1], thresholds[c]]`, where the first and last bounds are the
+/// minimum and maximum of the data.
+///
+/// * `data` - The input N-D data.
+/// * `classes` - Number of classes. `2` gives the same threshold as
+///   [`threshold_otsu`](crate::threshold_otsu).
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `classes < 2`, if there are less than `classes` non-empty bins in the histogram,
+/// if `mask` doesn't have the same shape as `data` or if there's no voxel to use.
+pub fn threshold_multiotsu<A, D>(
+    data: &ArrayRef<A, D>,
+    classes: usize,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> Vec<f64>
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    assert!(classes >= 2, "`classes` must be at least 2");
+    let (hist, centers) = histogram(&values(data, mask), nbins);
+
+    // Only the non-empty bins can end a class
+    let (hist, centers): (Vec<_>, Vec<_>) =
+        hist.into_iter().zip(centers).filter(|&(h, _)| h > 0.0).unzip();
+    let nb_bins = hist.len();
+    assert!(nb_bins >= classes, "The histogram has less non-empty bins than `classes`");
+
+    // Maximizing the between-class variance is equivalent to maximizing `Σ S_c² / W_c`, where
+    // `S_c` is the sum and `W_c` the weight of the class `c`. It's solved by dynamic programming.
+    let mut weights = vec![0.0; nb_bins + 1];
+    let mut sums = vec![0.0; nb_bins + 1];
+    for i in 0..nb_bins {
+        weights[i + 1] = weights[i] + hist[i];
+        sums[i + 1] = sums[i] + hist[i] * centers[i];
+    }
+    let score = |start: usize, end: usize| {
+        let s = sums[end] - sums[start];
+        s * s / (weights[end] - weights[start])
+    };
+
+    // `best[c][j]` is the best score of `c + 1` classes covering the first `j` bins, and
+    // `previous[c][j]` is the number of bins covered by the first `c` classes of this solution.
+    let mut best = vec![vec![f64::NEG_INFINITY; nb_bins + 1]; classes];
+    let mut previous = vec![vec![0; nb_bins + 1]; classes];
+    for (j, b) in best[0].iter_mut().enumerate().skip(1) {
+        *b = score(0, j);
+    }
+    for c in 1..classes {
+        for j in c + 1..=nb_bins {
+            for k in c..j {
+                let candidate = best[c - 1][k] + score(k, j);
+                if candidate > best[c][j] {
+                    best[c][j] = candidate;
+                    previous[c][j] = k;
+                }
+            }
+        }
+    }
+
+    let mut thresholds = vec![0.0; classes - 1];
+    let mut end = nb_bins;
+    for c in (1..classes).rev() {
+        end = previous[c][end];
+        thresholds[c - 1] = centers[end - 1];
+    }
+    thresholds
+}
+
+/// Computes a threshold with Li's iterative minimum cross-entropy method.
+///
+/// The foreground is `data > threshold`. The histogram is not used, the method works directly on
+/// the values.
+///
+/// * `data` - The input N-D data.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `mask` doesn't have the same shape as `data` or if there's no voxel to use.
+pub fn threshold_li<A, D>(data: &ArrayRef<A, D>, mask: Option<&ArrayRef<bool, D>>) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let mut values = values(data, mask);
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    let min = values[0];
+    values.iter_mut().for_each(|v| *v -= min);
+
+    // Half the smallest difference between two distinct values
+    let tolerance =
+        values.windows(2).map(|w| w[1] - w[0]).filter(|&d| d > 0.0).fold(f64::INFINITY, f64::min)
+            / 2.0;
+    if tolerance.is_infinite() {
+        return min;
+    }
+
+    let mut next = values.iter().sum::<f64>() / values.len() as f64;
+    let mut current = -2.0 * tolerance;
+    while (next - current).abs() > tolerance {
+        current = next;
+        // `values` is sorted, so the foreground is at the end
+        let split = values.partition_point(|&v| v <= current);
+        let (background, foreground) = values.split_at(split);
+        let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+        let (mean_back, mean_fore) = (mean(background), mean(foreground));
+        if mean_back == 0.0 || foreground.is_empty() {
+            break;
+        }
+        next = (mean_back - mean_fore) / (mean_back.ln() - mean_fore.ln());
+    }
+    next + min
+}
+
+/// Computes a threshold with Yen's method, which maximizes the entropic correlation.
+///
+/// The foreground is `data > threshold`.
+///
+/// * `data` - The input N-D data.
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `nbins` is 0, if `mask` doesn't have the same shape as `data` or if there's no
+/// voxel to use.
+pub fn threshold_yen<A, D>(
+    data: &ArrayRef<A, D>,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let (hist, centers) = histogram(&values(data, mask), nbins);
+    if centers.len() == 1 {
+        return centers[0];
+    }
+
+    let total: f64 = hist.iter().sum();
+    let pmf: Vec<_> = hist.iter().map(|&h| h / total).collect();
+    let nb_bins = pmf.len();
+    let mut p1_sq_reversed = vec![0.0; nb_bins];
+    let mut acc = 0.0;
+    for i in (0..nb_bins).rev() {
+        acc += pmf[i] * pmf[i];
+        p1_sq_reversed[i] = acc;
+    }
+
+    let (mut p1, mut p1_sq) = (0.0, 0.0);
+    let mut best = (f64::NEG_INFINITY, 0);
+    for i in 0..nb_bins - 1 {
+        p1 += pmf[i];
+        p1_sq += pmf[i] * pmf[i];
+        let p2_sq = p1_sq_reversed[i + 1];
+        let product = p1 * (1.0 - p1);
+        if p1_sq == 0.0 || p2_sq == 0.0 || product <= 0.0 {
+            continue;
+        }
+        let criterion = 2.0 * product.ln() - (p1_sq * p2_sq).ln();
+        if criterion > best.0 {
+            best = (criterion, i);
+        }
+    }
+    centers[best.1]
+}
+
+/// Computes a threshold with the triangle algorithm.
+///
+/// A line is drawn between the peak of the histogram and the farthest end of the histogram. The
+/// threshold is the bin with the largest distance to this line. It works best when the foreground
+/// is a small peak on the side of a large background peak. The foreground is `data > threshold`
+/// when the background peak is on the dark side of the histogram.
+///
+/// * `data` - The input N-D data.
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `nbins` is 0, if `mask` doesn't have the same shape as `data` or if there's no
+/// voxel to use.
+pub fn threshold_triangle<A, D>(
+    data: &ArrayRef<A, D>,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let (mut hist, centers) = histogram(&values(data, mask), nbins);
+    let nb_bins = hist.len();
+    let arg_peak = (0..nb_bins).fold(0, |best, i| if hist[i] > hist[best] { i } else { best });
+    let low = hist.iter().position(|&h| h > 0.0).unwrap();
+    let high = hist.iter().rposition(|&h| h > 0.0).unwrap();
+    if low == high {
+        return centers[low];
+    }
+
+    // The algorithm is written for a peak on the right side of the histogram
+    let flip = arg_peak - low < high - arg_peak;
+    let (low, arg_peak) = if flip {
+        hist.reverse();
+        (nb_bins - high - 1, nb_bins - arg_peak - 1)
+    } else {
+        (low, arg_peak)
+    };
+
+    let width = (arg_peak - low) as f64;
+    let norm = (hist[arg_peak] * hist[arg_peak] + width * width).sqrt();
+    let (peak_height, width) = (hist[arg_peak] / norm, width / norm);
+    let mut best = (f64::NEG_INFINITY, low);
+    for x in 0..arg_peak - low {
+        let length = peak_height * x as f64 - width * hist[x + low];
+        if length > best.0 {
+            best = (length, x + low);
+        }
+    }
+
+    let level = if flip { nb_bins - best.1 - 1 } else { best.1 };
+    centers[level]
+}
+
+/// Computes a threshold with the isodata (Ridler-Calvard) method.
+///
+/// The threshold is the first bin center for which the threshold is equal to the mean of the
+/// background and foreground means, which are respectively `data <= threshold` and
+/// `data > threshold`.
+///
+/// * `data` - The input N-D data.
+/// * `nbins` - Number of bins of the histogram. Usually 256.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `nbins` is 0, if `mask` doesn't have the same shape as `data` or if there's no
+/// voxel to use.
+pub fn threshold_isodata<A, D>(
+    data: &ArrayRef<A, D>,
+    nbins: usize,
+    mask: Option<&ArrayRef<bool, D>>,
+) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let (hist, centers) = histogram(&values(data, mask), nbins);
+    if centers.len() == 1 {
+        return centers[0];
+    }
+
+    let total_weight: f64 = hist.iter().sum();
+    let total_sum: f64 = hist.iter().zip(&centers).map(|(&h, &c)| h * c).sum();
+    let bin_width = centers[1] - centers[0];
+    let (mut weight, mut sum) = (0.0, 0.0);
+    let mut closest = (f64::INFINITY, 0);
+    for i in 0..hist.len() - 1 {
+        weight += hist[i];
+        sum += hist[i] * centers[i];
+        if weight == 0.0 || weight == total_weight {
+            continue;
+        }
+        let lower = sum / weight;
+        let higher = (total_sum - sum) / (total_weight - weight);
+        let distance = (lower + higher) / 2.0 - centers[i];
+        if (0.0..bin_width).contains(&distance) {
+            return centers[i];
+        }
+        // Fallback if no bin satisfies the condition, because of the discretization
+        let error = (distance - bin_width / 2.0).abs();
+        if error < closest.0 {
+            closest = (error, i);
+        }
+    }
+    centers[closest.1]
+}
+
+/// Returns the mean of the values, which can be used as a threshold.
+///
+/// * `data` - The input N-D data.
+/// * `mask` - Optional mask restricting the voxels used to the `true` values.
+///
+/// **Panics** if `mask` doesn't have the same shape as `data` or if there's no voxel to use.
+pub fn threshold_mean<A, D>(data: &ArrayRef<A, D>, mask: Option<&ArrayRef<bool, D>>) -> f64
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let values = values(data, mask);
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Returns the values of `data` selected by `mask`, as `f64`.
+pub(crate) fn values<A, D>(data: &ArrayRef<A, D>, mask: Option<&ArrayRef<bool, D>>) -> Vec<f64>
+where
+    A: Copy + ToPrimitive,
+    D: Dimension,
+{
+    let values = match mask {
+        Some(mask) => {
+            assert_eq!(mask.shape(), data.shape(), "`mask` must have the same shape as `data`");
+            let mut values = vec![];
+            Zip::from(data).and(mask).for_each(|&v, &m| {
+                if m {
+                    values.push(v.to_f64().unwrap());
+                }
+            });
+            values
+        }
+        None => data.iter().map(|v| v.to_f64().unwrap()).collect(),
+    };
+    assert!(!values.is_empty(), "There's no voxel to use");
+    values
+}
+
+/// Returns the histogram of `values` with `nbins` bins of the same width, and the center of the
+/// bins. A single bin is returned if all values are equal.
+pub(crate) fn histogram(values: &[f64], nbins: usize) -> (Vec<f64>, Vec<f64>) {
+    assert!(nbins > 0, "`nbins` must be greater than 0");
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| (min.min(v), max.max(v)));
+    if min == max {
+        return (vec![values.len() as f64], vec![min]);
+    }
+
+    let width = (max - min) / nbins as f64;
+    let mut hist = vec![0.0; nbins];
+    for &v in values {
+        let bin = (((v - min) / width) as usize).min(nbins - 1);
+        hist[bin] += 1.0;
+    }
+    let centers = (0..nbins).map(|i| min + width * (i as f64 + 0.5)).collect();
+    (hist, centers)
+}
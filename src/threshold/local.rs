@@ -0,0 +1,164 @@
+use ndarray::{indices, Array, ArrayRef, Dimension, IxDyn, Zip};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{
+    filters::symmetry::SymmetryStateCheck, gaussian_filter, pad, uniform_filter, BorderMode,
+};
+
+/// Method used by [`threshold_local`](crate::threshold_local) to compute the local statistic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocalThresholdMethod {
+    /// Gaussian weighted mean, with a standard deviation of `(block_size - 1) / 6`.
+    Gaussian,
+    /// Arithmetic mean of the block.
+    Mean,
+    /// Median of the block.
+    Median,
+}
+
+/// Computes a threshold map based on the local neighborhood of each voxel.
+///
+/// The threshold of a voxel is a statistic of the block of length `block_size` centered on it,
+/// minus `offset`. Compare `data` to the returned map to get the foreground, for example with
+/// `Zip::from(&data).and(&thresholds).map_collect(|&v, &t| v > t)`. This is useful when the
+/// background intensity is not uniform across the image.
+///
+/// * `data` - The input N-D data.
+/// * `block_size` - Length of the blocks used to compute the local statistic. Must be odd.
+/// * `method` - The local statistic. See the [`LocalThresholdMethod`](crate::LocalThresholdMethod)
+///   enum for more information.
+/// * `offset` - Constant subtracted from the local statistic.
+/// * `mode` - Method that will be used to select the padded values. See the
+///   [`BorderMode`](crate::BorderMode) enum for more information.
+///
+/// **Panics** if `block_size` is even or if one of the axis' lengths is lower than `block_size`.
+pub fn threshold_local<A, D>(
+    data: &ArrayRef<A, D>,
+    block_size: usize,
+    method: LocalThresholdMethod,
+    offset: A,
+    mode: BorderMode<A>,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    for<'a> &'a [A]: SymmetryStateCheck,
+    D: Dimension,
+{
+    assert!(block_size % 2 == 1, "`block_size` must be odd");
+    let mut thresholds = match method {
+        LocalThresholdMethod::Gaussian => {
+            let sigma = A::from_usize(block_size - 1).unwrap() / A::from_usize(6).unwrap();
+            gaussian_filter(data, sigma, 0, mode, 4)
+        }
+        LocalThresholdMethod::Mean => uniform_filter(data, block_size, mode),
+        LocalThresholdMethod::Median => median(data, block_size, mode),
+    };
+    thresholds.mapv_inplace(|t| t - offset);
+    thresholds
+}
+
+/// Computes a threshold map with Niblack's method.
+///
+/// The threshold of a voxel is `m - k * s`, where `m` and `s` are the mean and standard deviation
+/// of the block of length `window_size` centered on it. The image is extended beyond its
+/// boundaries with the [`BorderMode::Mirror`](crate::BorderMode) mode, like scikit-image.
+///
+/// * `data` - The input N-D data.
+/// * `window_size` - Length of the blocks used to compute the local statistics. Must be odd.
+/// * `k` - Weight of the standard deviation. Usually `0.2`.
+///
+/// **Panics** if `window_size` is even or if one of the axis' lengths is lower than
+/// `window_size`.
+pub fn threshold_niblack<A, D>(data: &ArrayRef<A, D>, window_size: usize, k: A) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    D: Dimension,
+{
+    let (mut mean, std) = mean_std(data, window_size);
+    Zip::from(&mut mean).and(&std).for_each(|m, &s| *m = *m - k * s);
+    mean
+}
+
+/// Computes a threshold map with Sauvola's method.
+///
+/// The threshold of a voxel is `m * (1 + k * (s / r - 1))`, where `m` and `s` are the mean and
+/// standard deviation of the block of length `window_size` centered on it. Compared to Niblack's
+/// method, the threshold is lower in dark regions with a low contrast. The image is extended
+/// beyond its boundaries with the [`BorderMode::Mirror`](crate::BorderMode) mode, like
+/// scikit-image.
+///
+/// * `data` - The input N-D data.
+/// * `window_size` - Length of the blocks used to compute the local statistics. Must be odd.
+/// * `k` - Weight of the standard deviation. Usually `0.2`.
+/// * `r` - The dynamic range of the standard deviation. Defaults to half the range of `data`.
+///
+/// **Panics** if `window_size` is even or if one of the axis' lengths is lower than
+/// `window_size`.
+pub fn threshold_sauvola<A, D>(
+    data: &ArrayRef<A, D>,
+    window_size: usize,
+    k: A,
+    r: Option<A>,
+) -> Array<A, D>
+where
+    A: Float + FromPrimitive + 'static,
+    D: Dimension,
+{
+    let r = r.unwrap_or_else(|| {
+        let (min, max) = data
+            .iter()
+            .fold((A::infinity(), A::neg_infinity()), |(min, max), &v| (min.min(v), max.max(v)));
+        (max - min) / A::from_usize(2).unwrap()
+    });
+    let (mut mean, std) = mean_std(data, window_size);
+    Zip::from(&mut mean).and(&std).for_each(|m, &s| *m = *m * (A::one() + k * (s / r - A::one())));
+    mean
+}
+
+/// Returns the local mean and standard deviation of `data`, in blocks of length `window_size`.
+fn mean_std<A, D>(data: &ArrayRef<A, D>, window_size: usize) -> (Array<A, D>, Array<A, D>)
+where
+    A: Float + FromPrimitive + 'static,
+    D: Dimension,
+{
+    assert!(window_size % 2 == 1, "`window_size` must be odd");
+    let mean = uniform_filter(data, window_size, BorderMode::Mirror);
+    let mut std = uniform_filter(&data.mapv(|v| v * v), window_size, BorderMode::Mirror);
+    Zip::from(&mut std).and(&mean).for_each(|s, &m| *s = (*s - m * m).max(A::zero()).sqrt());
+    (mean, std)
+}
+
+/// Median filter with a cubic window of length `size`.
+fn median<A, D>(data: &ArrayRef<A, D>, size: usize, mode: BorderMode<A>) -> Array<A, D>
+where
+    A: Float + FromPrimitive,
+    D: Dimension,
+{
+    let half = size / 2;
+    let padded = pad(data, &[[half, half]], mode.to_pad_mode());
+    let padded = padded.view().into_dyn();
+
+    // Offsets of all voxels of the window in the padded image, relative to its first voxel
+    let strides = padded.strides();
+    let offsets: Vec<_> = indices(IxDyn(&vec![size; data.ndim()]))
+        .into_iter()
+        .map(|idx| (0..data.ndim()).fold(0, |acc, d| acc + idx[d] as isize * strides[d]))
+        .collect();
+
+    let padded = padded.as_slice_memory_order().unwrap();
+    let mut window = vec![A::zero(); offsets.len()];
+    let medians = indices(IxDyn(data.shape()))
+        .into_iter()
+        .map(|idx| {
+            let start = (0..data.ndim()).fold(0, |acc, d| acc + idx[d] as isize * strides[d]);
+            for (w, &offset) in window.iter_mut().zip(&offsets) {
+                *w = padded[(start + offset) as usize];
+            }
+            let (_, m, _) = window.select_nth_unstable_by(offsets.len() / 2, |a, b| {
+                a.partial_cmp(b).expect("Can't find median because of NaN values")
+            });
+            *m
+        })
+        .collect();
+    Array::from_shape_vec(data.raw_dim(), medians).unwrap()
+}
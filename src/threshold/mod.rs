@@ -0,0 +1,2 @@
+pub mod global;
+pub mod local;
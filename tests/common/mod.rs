@@ -0,0 +1,17 @@
+use ndarray::{Array, Dimension, ShapeBuilder};
+
+/// Deterministic pseudo-random values in `[0, 1)`, generated in the memory order of `shape`.
+pub fn noise<Sh, D>(shape: Sh) -> Array<f64, D>
+where
+    Sh: ShapeBuilder<Dim = D>,
+    D: Dimension,
+{
+    // xorshift64*
+    let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+    Array::from_shape_simple_fn(shape, || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
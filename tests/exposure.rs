@@ -0,0 +1,262 @@
+use approx::assert_relative_eq;
+use ndarray::{arr2, s, Array1, Array2, Array3};
+
+use ndarray_ndimage::{
+    equalize_adapthist, equalize_hist, match_histograms, normalize_zscore, rescale_intensity,
+    HistogramMatchingMethod, IntensityRange,
+};
+
+mod common;
+use common::noise;
+
+#[test] // Results are logical. The equalized histogram is flat.
+fn test_equalize_hist() {
+    // Skewed distribution
+    let a: Array1<f64> = (0..1000).map(|i| (i as f64 / 100.0).exp()).collect();
+    let equalized = equalize_hist(&a, 1000, None);
+    assert!(equalized.iter().all(|&v| (0.0..=1.0).contains(&v)));
+    assert!(equalized.windows(2).into_iter().all(|w| w[0] <= w[1]));
+    assert_relative_eq!(equalized[999], 1.0);
+    // Half of the values are in the first 1 / 150 of the range
+    assert!(equalized[500] > 0.4);
+
+    // Uniform distribution
+    let a: Array1<f64> = (0..256).map(|i| i as f64).collect();
+    let equalized = equalize_hist(&a, 256, None);
+    for (i, &v) in equalized.iter().enumerate() {
+        assert_relative_eq!(v, i as f64 / 255.0, epsilon = 2.0 / 256.0);
+    }
+
+    // Only the masked values are used for the histogram
+    let a = Array2::from_shape_fn((10, 10), |(x, y)| (x * 10 + y) as f64);
+    let mask = Array2::from_shape_fn((10, 10), |(x, _)| x < 5);
+    let equalized = equalize_hist(&a, 50, Some(&mask));
+    assert_relative_eq!(equalized[(4, 9)], 1.0);
+    assert!(equalized.slice(s![5.., ..]).iter().all(|&v| v == 1.0));
+    assert!(equalized[(0, 0)] < 0.05);
+}
+
+#[test] // Results are logical.
+fn test_equalize_adapthist() {
+    // A single tile without clipping is a histogram equalization, without interpolation. The
+    // output is rescaled to `[0, 1]` and quantized to the grey levels.
+    let a: Array2<f64> = Array2::from_shape_fn((8, 8), |(x, y)| (x * 8 + y) as f64);
+    let equalized = equalize_adapthist(&a, &[8], 0.0, 64);
+    for (i, &v) in equalized.iter().enumerate() {
+        assert_relative_eq!(v, i as f64 / 63.0, epsilon = 1e-4);
+    }
+
+    // Small details in a dark region are enhanced
+    let detail = noise((64, 64));
+    let a = Array2::from_shape_fn((64, 64), |(x, y)| {
+        let detail = detail[(x, y)];
+        if y < 32 {
+            0.05 * detail
+        } else {
+            0.8 + 0.2 * detail
+        }
+    });
+    let equalized = equalize_adapthist(&a, &[16, 16], 0.1, 256);
+    assert!(equalized.iter().all(|&v| (0.0..=1.0).contains(&v)));
+    let range = |a: &Array2<f64>| {
+        let dark = a.slice(s![20..40, 4..28]);
+        dark.fold(f64::NEG_INFINITY, |m, &v| m.max(v)) - dark.fold(f64::INFINITY, |m, &v| m.min(v))
+    };
+    assert!(range(&equalized) > 10.0 * range(&a));
+    // The clipping limits the contrast enhancement
+    let clipped = equalize_adapthist(&a, &[16, 16], 0.02, 256);
+    assert!(range(&clipped) < range(&equalized));
+    assert!(range(&clipped) > range(&a));
+
+    // Constant image
+    let a = Array3::from_elem((6, 6, 8), 3.0);
+    let equalized = equalize_adapthist(&a, &[3, 3, 4], 0.01, 256);
+    assert_relative_eq!(
+        equalized,
+        Array3::from_elem(a.dim(), equalized[(0, 0, 0)]),
+        epsilon = 1e-12
+    );
+}
+
+#[test] // Results verified with scikit-image
+fn test_equalize_adapthist_values() {
+    // The tiles don't divide the shape, so the image is extended by reflection
+    let a = arr2(&[
+        [0.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 35.0, 40.0],
+        [7.0, 13.0, 19.0, 25.0, 31.0, 37.0, 43.0, 49.0, 55.0],
+        [14.0, 21.0, 28.0, 35.0, 42.0, 49.0, 45.0, 52.0, 59.0],
+        [21.0, 29.0, 37.0, 45.0, 42.0, 50.0, 58.0, 1.0, 63.0],
+        [28.0, 37.0, 46.0, 44.0, 53.0, 62.0, 60.0, 4.0, 13.0],
+        [35.0, 45.0, 55.0, 54.0, 64.0, 63.0, 8.0, 7.0, 17.0],
+        [42.0, 53.0, 53.0, 64.0, 64.0, 10.0, 10.0, 21.0, 64.0],
+    ]);
+    let equalized = equalize_adapthist(&a, &[3, 4], 0.15, 16);
+    let gt = arr2(&[
+        [0.0, 0.090891, 0.181782, 0.249967, 0.318152, 0.409042, 0.454521, 0.52264, 0.590891],
+        [0.090891, 0.272739, 0.36363, 0.499933, 0.499933, 0.63637, 0.727261, 0.88627, 0.909042],
+        [0.272739, 0.454521, 0.545412, 0.613597, 0.727261, 0.909042, 0.818152, 0.88627, 1.0],
+        [0.393861, 0.515115, 0.63637, 0.833267, 0.757558, 0.931749, 1.0, 0.0, 1.0],
+        [0.424224, 0.63637, 0.818152, 0.757558, 0.969636, 1.0, 1.0, 0.0, 0.227261],
+        [0.545412, 0.818152, 1.0, 1.0, 1.0, 1.0, 0.090891, 0.090891, 0.36363],
+        [0.787855, 1.0, 1.0, 1.0, 1.0, 0.1666, 0.181782, 0.454521, 1.0],
+    ]);
+    assert_relative_eq!(equalized, gt, epsilon = 1e-5);
+}
+
+#[test] // Results are logical. A 3D image made of identical slices gives the same slices as in 2D.
+fn test_equalize_adapthist_3d() {
+    let a = Array2::from_shape_fn((20, 24), |(x, y)| {
+        ((x as f64 / 3.0).sin() + (y as f64 / 4.0).cos()) * 10.0
+    });
+    let equalized = equalize_adapthist(&a, &[5, 6], 0.1, 128);
+    let a3 = Array3::from_shape_fn((7, 20, 24), |(_, x, y)| a[(x, y)]);
+    let equalized3 = equalize_adapthist(&a3, &[7, 5, 6], 0.1, 128);
+    for slice in equalized3.outer_iter() {
+        // The interpolated grey levels are truncated, so the results can differ by one level
+        assert_relative_eq!(slice, equalized, epsilon = 1e-4);
+    }
+    assert!(equalized.iter().all(|&v| (0.0..=1.0).contains(&v)));
+}
+
+#[test] // Results are logical.
+fn test_match_histograms_exact() {
+    // Same number of voxels, so each value takes the reference value of the same rank
+    let source: Array1<f64> = (0..100).map(|i| ((i * 37) % 100) as f64).collect();
+    let reference = Array2::from_shape_fn((10, 10), |(x, y)| ((x * 10 + y) as f64).powi(2));
+    let matched = match_histograms(&source, &reference, None, None, HistogramMatchingMethod::Exact);
+    for (&s, &m) in source.iter().zip(&matched) {
+        assert_relative_eq!(m, s * s);
+    }
+
+    // Only the masked voxels are used. The other values are clamped to the reference range.
+    let source: Array1<f64> = (0..20).map(|i| i as f64).collect();
+    let source_mask = Array1::from_shape_fn(20, |i| i < 10);
+    let reference: Array1<f64> =
+        (0..20).map(|i| if i < 10 { 100.0 + i as f64 } else { -1.0 }).collect();
+    let reference_mask = reference.mapv(|v| v >= 0.0);
+    let matched = match_histograms(
+        &source,
+        &reference,
+        Some(&source_mask),
+        Some(&reference_mask),
+        HistogramMatchingMethod::Exact,
+    );
+    for i in 0..10 {
+        assert_relative_eq!(matched[i], 100.0 + i as f64);
+    }
+    assert!(matched.slice(s![10..]).iter().all(|&v| v == 109.0));
+}
+
+#[test] // Results are logical.
+fn test_match_histograms_landmarks() {
+    // An affine transformation of the intensities is recovered, even outside of the landmarks
+    let source = noise((6, 7, 8)) - 0.5;
+    let reference = source.mapv(|v| 3.0 * v + 10.0);
+    let matched =
+        match_histograms(&source, &reference, None, None, HistogramMatchingMethod::Landmarks);
+    assert_relative_eq!(matched, reference, epsilon = 1e-9);
+
+    // The landmarks ignore the voxels outside of the masks
+    let mut reference = reference.slice(s![..3, .., ..]).to_owned();
+    let mut reference_mask = Array3::from_elem(reference.dim(), true);
+    reference.slice_mut(s![.., 0, ..]).fill(1000.0);
+    reference_mask.slice_mut(s![.., 0, ..]).fill(false);
+    let matched = match_histograms(
+        &source,
+        &reference,
+        None,
+        Some(&reference_mask),
+        HistogramMatchingMethod::Landmarks,
+    );
+    assert!(matched.iter().all(|&v| (8.0..12.0).contains(&v)));
+
+    // A constant source doesn't produce NaN values
+    let source = Array1::from_elem(10, 2.0);
+    let reference: Array1<f64> = (0..10).map(|i| i as f64).collect();
+    let matched =
+        match_histograms(&source, &reference, None, None, HistogramMatchingMethod::Landmarks);
+    assert!(matched.iter().all(|v| v.is_finite()));
+    let matched = match_histograms(
+        &source.mapv(|v| v * 1e9),
+        &reference,
+        None,
+        None,
+        HistogramMatchingMethod::Landmarks,
+    );
+    assert!(matched.iter().all(|v| v.is_finite()));
+}
+
+#[test] // Results are logical.
+fn test_rescale_intensity() {
+    let a: Array1<f64> = (0..=100).map(|i| i as f64).collect();
+    let rescaled = rescale_intensity(&a, IntensityRange::Image, (-1.0, 1.0), None);
+    for (i, &v) in rescaled.iter().enumerate() {
+        assert_relative_eq!(v, i as f64 / 50.0 - 1.0, epsilon = 1e-12);
+    }
+
+    // The values outside of the input range are clipped
+    let rescaled = rescale_intensity(&a, IntensityRange::Values(20.0, 70.0), (0.0, 1.0), None);
+    assert_eq!(rescaled[10], 0.0);
+    assert_relative_eq!(rescaled[45], 0.5);
+    assert_eq!(rescaled[90], 1.0);
+
+    // The percentiles ignore the outliers
+    let mut a = Array2::from_shape_fn((20, 10), |(x, y)| (x * 10 + y) as f64);
+    a[(0, 0)] = -1e6;
+    a[(19, 9)] = 1e6;
+    let rescaled = rescale_intensity(&a, IntensityRange::Percentiles(1.0, 99.0), (0.0, 1.0), None);
+    assert_eq!(rescaled[(0, 0)], 0.0);
+    assert_eq!(rescaled[(19, 9)], 1.0);
+    assert!(rescaled[(10, 0)] > 0.4 && rescaled[(10, 0)] < 0.6);
+
+    // Only the masked voxels are used to compute the range
+    let mask = Array2::from_shape_fn((20, 10), |(x, _)| x >= 10);
+    let rescaled =
+        rescale_intensity(&a, IntensityRange::Percentiles(0.0, 100.0), (0.0, 1.0), Some(&mask));
+    assert_relative_eq!(rescaled[(10, 0)], 0.0);
+    assert_relative_eq!(rescaled[(19, 9)], 1.0);
+    assert!(rescaled.slice(s![..10, ..]).iter().all(|&v| v == 0.0));
+
+    // Integer types are rounded
+    let a = Array1::from(vec![0u16, 1000, 2000, 4095]);
+    let rescaled = rescale_intensity(&a, IntensityRange::Image, (0, 255), None);
+    assert_eq!(rescaled, Array1::from(vec![0, 62, 125, 255]));
+    let a = Array1::from(vec![10u8, 20, 30]);
+    let rescaled = rescale_intensity(&a, IntensityRange::Values(10, 30), (0, 255), None);
+    assert_eq!(rescaled, Array1::from(vec![0, 128, 255]));
+    // Even the 64-bit integers, whose bounds can't be represented exactly in `f64`
+    let a = Array1::from(vec![0u64, 5, 10]);
+    let rescaled = rescale_intensity(&a, IntensityRange::Image, (0, u64::MAX), None);
+    assert_eq!(rescaled, Array1::from(vec![0, 1 << 63, u64::MAX]));
+    let a = Array1::from(vec![-3i64, 0, 3]);
+    let rescaled = rescale_intensity(&a, IntensityRange::Image, (i64::MIN, i64::MAX), None);
+    assert_eq!(rescaled, Array1::from(vec![i64::MIN, 0, i64::MAX]));
+
+    // Constant image
+    let a = Array1::from_elem(5, 3.0);
+    let rescaled = rescale_intensity(&a, IntensityRange::Image, (0.0, 1.0), None);
+    assert_eq!(rescaled, Array1::zeros(5));
+}
+
+#[test] // Results are logical.
+fn test_normalize_zscore() {
+    let a = noise((6, 7, 8)) * 50.0 + 100.0;
+    let normalized = normalize_zscore(&a, None);
+    let n = normalized.len() as f64;
+    let mean = normalized.sum() / n;
+    assert_relative_eq!(mean, 0.0, epsilon = 1e-12);
+    assert_relative_eq!(normalized.mapv(|v| v * v).sum() / n, 1.0, epsilon = 1e-12);
+
+    // Only the masked voxels are used. The background is far below the foreground.
+    let mut a = Array2::from_shape_fn((10, 10), |(x, y)| ((x + y) % 4) as u8 + 100);
+    a.slice_mut(s![..5, ..]).fill(0);
+    let mask = a.mapv(|v| v > 0);
+    let normalized = normalize_zscore(&a, Some(&mask));
+    let foreground = normalized.slice(s![5.., ..]);
+    assert_relative_eq!(foreground.sum() / 50.0, 0.0, epsilon = 1e-12);
+    assert!(normalized[(0, 0)] < -50.0);
+
+    // Constant image
+    let normalized = normalize_zscore(&Array1::from_elem(4, 7u8), None);
+    assert_eq!(normalized, Array1::zeros(4));
+}
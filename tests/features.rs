@@ -0,0 +1,124 @@
+use approx::assert_relative_eq;
+use ndarray::{s, Array2, Array3};
+
+use ndarray_ndimage::{blob_dog, blob_log, canny};
+
+#[test] // Results are logical. The edges of a square are its outline.
+fn test_canny_2d() {
+    let mut a = Array2::zeros((20, 20));
+    a.slice_mut(s![5..15, 5..15]).fill(1.0);
+
+    let edges = canny(&a, 1.0, 0.1, 0.2, None);
+    assert!(edges.iter().any(|&e| e));
+    // The edges are one voxel thick, on one side of the step
+    for x in 7..13 {
+        assert_eq!(edges.slice(s![x, ..]).iter().filter(|&&e| e).count(), 2);
+        assert_eq!(edges.slice(s![.., x]).iter().filter(|&&e| e).count(), 2);
+        assert!(edges[(x, 4)] || edges[(x, 5)]);
+        assert!(edges[(x, 14)] || edges[(x, 15)]);
+    }
+    assert!(edges.slice(s![7..13, 7..13]).iter().all(|&e| !e));
+    assert!(edges.slice(s![..3, ..]).iter().all(|&e| !e));
+
+    // The thresholds are too high
+    assert!(canny(&a, 1.0, 10.0, 20.0, None).iter().all(|&e| !e));
+
+    // Only the voxels inside the mask (and not on its border) can be edges
+    let mut mask = Array2::from_elem(a.dim(), false);
+    mask.slice_mut(s![..10, ..]).fill(true);
+    let masked = canny(&a, 1.0, 0.1, 0.2, Some(&mask));
+    assert!(masked.slice(s![9.., ..]).iter().all(|&e| !e));
+    assert_eq!(masked.slice(s![..9, ..]), edges.slice(s![..9, ..]));
+}
+
+#[test] // Results are logical. The weak edges are kept only when connected to strong edges.
+fn test_canny_hysteresis() {
+    // A strong step on the left, a weaker step on the right
+    let mut a = Array2::zeros((20, 20));
+    a.slice_mut(s![.., 5..]).fill(2.0);
+    a.slice_mut(s![.., 14..]).fill(2.5);
+
+    let edges = canny(&a, 1.0, 0.5, 2.0, None);
+    assert!(edges.slice(s![2..18, 3..7]).iter().any(|&e| e));
+    assert!(edges.slice(s![.., 10..]).iter().all(|&e| !e));
+
+    let edges = canny(&a, 1.0, 0.5, 0.5, None);
+    assert!(edges.slice(s![2..18, 12..16]).iter().any(|&e| e));
+}
+
+#[test] // Results are logical. The edges of a cube are its outline.
+fn test_canny_3d() {
+    let mut a = Array3::zeros((16, 16, 16));
+    a.slice_mut(s![4..12, 4..12, 4..12]).fill(1.0);
+
+    let edges = canny(&a, 1.0, 0.1, 0.5, None);
+    assert!(edges.slice(s![6..10, 6..10, 6..10]).iter().all(|&e| !e));
+    assert!(edges.slice(s![..2, .., ..]).iter().all(|&e| !e));
+    for x in 6..10 {
+        assert_eq!(edges.slice(s![x, 8, ..]).iter().filter(|&&e| e).count(), 2);
+        assert_eq!(edges.slice(s![8, x, ..]).iter().filter(|&&e| e).count(), 2);
+        assert_eq!(edges.slice(s![.., 8, x]).iter().filter(|&&e| e).count(), 2);
+    }
+}
+
+fn gaussian_blobs_2d(blobs: &[([f64; 2], f64)]) -> Array2<f64> {
+    Array2::from_shape_fn((40, 44), |(x, y)| {
+        blobs.iter().fold(0.0, |acc, &([cx, cy], s)| {
+            let d2 = (x as f64 - cx).powi(2) + (y as f64 - cy).powi(2);
+            acc + (-d2 / (2.0 * s * s)).exp()
+        })
+    })
+}
+
+#[test] // Results are logical. A Gaussian blob is detected at the scale of its standard deviation.
+fn test_blob_log() {
+    let a = gaussian_blobs_2d(&[([10.3, 12.0], 2.0), ([26.0, 29.6], 3.5)]);
+    let mut blobs = blob_log(&a, 1.0, 5.0, 17, 0.1, 0.5);
+    blobs.sort_by(|a, b| a.sigma.partial_cmp(&b.sigma).unwrap());
+    assert_eq!(blobs.len(), 2);
+    assert_relative_eq!(blobs[0].center[0], 10.3, epsilon = 0.1);
+    assert_relative_eq!(blobs[0].center[1], 12.0, epsilon = 0.1);
+    assert_relative_eq!(blobs[0].sigma, 2.0, epsilon = 0.25);
+    assert_relative_eq!(blobs[1].center[0], 26.0, epsilon = 0.1);
+    assert_relative_eq!(blobs[1].center[1], 29.6, epsilon = 0.1);
+    assert_relative_eq!(blobs[1].sigma, 3.5, epsilon = 0.25);
+
+    // Too high threshold
+    assert!(blob_log(&a, 1.0, 5.0, 17, 10.0, 0.5).is_empty());
+
+    let a = Array3::from_shape_fn((20, 20, 20), |(x, y, z)| {
+        let d2 = (x as f64 - 9.0).powi(2) + (y as f64 - 10.4).powi(2) + (z as f64 - 8.8).powi(2);
+        (-d2 / (2.0 * 2.5 * 2.5)).exp()
+    });
+    let blobs = blob_log(&a, 1.0, 4.0, 13, 0.1, 0.5);
+    assert_eq!(blobs.len(), 1);
+    assert_relative_eq!(&blobs[0].center[..], &[9.0, 10.4, 8.8][..], epsilon = 0.1);
+    // In 3D, the scale-normalized LoG of a Gaussian blob peaks at `sqrt(2 / 3)` times its width
+    assert_relative_eq!(blobs[0].sigma, 2.5 * (2.0f64 / 3.0).sqrt(), epsilon = 0.1);
+}
+
+#[test] // Results are logical. A Gaussian blob is detected at the scale of its standard deviation.
+fn test_blob_dog() {
+    let a = gaussian_blobs_2d(&[([10.3, 12.0], 2.0), ([26.0, 29.6], 3.5)]);
+    let mut blobs = blob_dog(&a, 1.0, 5.0, 1.2, 0.05, 0.5);
+    blobs.sort_by(|a, b| a.sigma.partial_cmp(&b.sigma).unwrap());
+    assert_eq!(blobs.len(), 2);
+    assert_relative_eq!(blobs[0].center[0], 10.3, epsilon = 0.1);
+    assert_relative_eq!(blobs[0].center[1], 12.0, epsilon = 0.1);
+    // The reported scale is the lower one of the two Gaussian kernels
+    assert!(blobs[0].sigma > 2.0 / 1.2 && blobs[0].sigma < 2.0);
+    assert_relative_eq!(blobs[1].center[0], 26.0, epsilon = 0.1);
+    assert_relative_eq!(blobs[1].center[1], 29.6, epsilon = 0.1);
+    assert!(blobs[1].sigma > 3.5 / 1.2 && blobs[1].sigma < 3.5);
+
+    // Overlapping blobs
+    let a = gaussian_blobs_2d(&[([20.0, 20.0], 3.0), ([20.0, 21.0], 1.0)]);
+    assert_eq!(blob_dog(&a, 1.0, 5.0, 1.2, 0.05, 0.5).len(), 1);
+}
+
+#[should_panic]
+#[test]
+fn test_blob_log_zero_sigma() {
+    let a = Array2::<f64>::zeros((30, 30));
+    let _ = blob_log(&a, 0.0, 5.0, 6, 0.1, 0.5);
+}
@@ -1,12 +1,17 @@
 use approx::assert_relative_eq;
-use ndarray::{arr1, arr2, s, Array1, Array2, Axis};
+use ndarray::{arr1, arr2, s, Array1, Array2, Array3, Axis, Zip};
 
 use ndarray_ndimage::{
-    convolve, convolve1d, correlate, correlate1d, gaussian_filter, maximum_filter,
-    maximum_filter1d, median_filter, minimum_filter, minimum_filter1d, prewitt, sobel,
-    uniform_filter, BorderMode, Mask,
+    convolve, convolve1d, correlate, correlate1d, difference_of_gaussians, entropy_filter, frangi,
+    gaussian_filter, guided_filter, hessian_eigenvalues, hessian_matrix, local_histogram,
+    maximum_filter, maximum_filter1d, median_filter, meijering, minimum_filter, minimum_filter1d,
+    non_local_means, non_local_means_blockwise, prewitt, sato, sobel, structure_tensor,
+    structure_tensor_eigenvalues, uniform_filter, unsharp_mask, BorderMode, Mask,
 };
 
+mod common;
+use common::noise;
+
 #[test] // Results verified with SciPy. (v1.9.0)
 fn test_convolve1d() {
     let arr = arr1(&[2.0, 8.0, 0.0, 4.0, 1.0, 9.0, 9.0, 0.0]);
@@ -670,6 +675,32 @@ fn test_gaussian_filter_panic() {
     let _ = gaussian_filter(&a, 2.0, 0, BorderMode::Reflect, 4);
 }
 
+#[test] // Results are logical.
+fn test_difference_of_gaussians() {
+    let a = Array2::from_elem((15, 16), 3.5);
+    assert_relative_eq!(
+        difference_of_gaussians(&a, 1.0, 2.0),
+        Array2::zeros((15, 16)),
+        epsilon = 1e-12
+    );
+
+    // A small bright spot is enhanced at its center and surrounded by a dark ring
+    let mut a = Array2::<f64>::zeros((21, 21));
+    a[(10, 10)] = 1.0;
+    let dog = difference_of_gaussians(&a, 1.0, 2.0);
+    assert!(dog[(10, 10)] > 0.0);
+    assert!(dog[(10, 14)] < 0.0);
+    assert_relative_eq!(dog.sum(), 0.0, epsilon = 1e-10);
+    assert_eq!(dog, dog.t());
+}
+
+#[should_panic]
+#[test]
+fn test_difference_of_gaussians_panic() {
+    let a = Array2::<f64>::zeros((21, 21));
+    let _ = difference_of_gaussians(&a, 2.0, 1.0);
+}
+
 #[test] // Results verified with SciPy. (v1.9.1)
 fn test_uniform_filter_1d() {
     let a: Array1<f32> = (0..7).map(|v| v as f32).collect();
@@ -1077,3 +1108,338 @@ fn test_sobel() {
         epsilon = 1e-5
     );
 }
+
+#[test] // Results are logical. A constant image can't be denoised.
+fn test_non_local_means_constant() {
+    let a = Array3::from_elem((6, 7, 8), 2.5);
+    for mode in [BorderMode::Reflect, BorderMode::Nearest, BorderMode::Mirror] {
+        assert_relative_eq!(non_local_means(&a, 3, 2, 0.5, mode, None), a, epsilon = 1e-10);
+        assert_relative_eq!(
+            non_local_means_blockwise(&a, 3, 2, 0.5, 2, mode, None),
+            a,
+            epsilon = 1e-10
+        );
+    }
+
+    // With Rician noise, the bias `2 * sigma²` is removed from the squared magnitudes
+    let a = Array3::from_elem((5, 5, 5), 5.0);
+    let gt = Array3::from_elem((5, 5, 5), (25.0f64 - 2.0 * 1.5 * 1.5).sqrt());
+    assert_relative_eq!(
+        non_local_means(&a, 3, 1, 1.0, BorderMode::Reflect, Some(1.5)),
+        gt,
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(
+        non_local_means_blockwise(&a, 3, 1, 1.0, 3, BorderMode::Reflect, Some(1.5)),
+        gt,
+        epsilon = 1e-10
+    );
+}
+
+#[test] // Results are logical. The noise must be reduced and the edge preserved.
+fn test_non_local_means_denoising() {
+    let clean = Array3::from_shape_fn((10, 10, 10), |(_, _, z)| if z < 5 { 0.0 } else { 10.0 });
+    let noisy = &clean + noise((10, 10, 10)) - 0.5;
+    let error = |a: &Array3<f64>| (a - &clean).mapv(|v| v * v).mean().unwrap();
+
+    let denoised = non_local_means(&noisy, 3, 2, 1.0, BorderMode::Reflect, None);
+    assert!(error(&denoised) < error(&noisy) / 4.0);
+    assert!(denoised.slice(s![.., .., ..4]).iter().all(|&v| v < 1.0));
+    assert!(denoised.slice(s![.., .., 6..]).iter().all(|&v| v > 9.0));
+
+    let denoised = non_local_means_blockwise(&noisy, 3, 2, 1.0, 2, BorderMode::Reflect, None);
+    assert!(error(&denoised) < error(&noisy) / 4.0);
+    assert!(denoised.slice(s![.., .., ..4]).iter().all(|&v| v < 1.0));
+    assert!(denoised.slice(s![.., .., 6..]).iter().all(|&v| v > 9.0));
+}
+
+#[should_panic]
+#[test]
+fn test_non_local_means_even_patch() {
+    let a = Array3::from_elem((5, 5, 5), 1.0);
+    let _ = non_local_means(&a, 2, 1, 1.0, BorderMode::Reflect, None);
+}
+
+#[should_panic]
+#[test]
+fn test_non_local_means_blockwise_empty() {
+    let a = Array3::<f64>::zeros((5, 0, 5));
+    let _ = non_local_means_blockwise(&a, 3, 1, 1.0, 2, BorderMode::Constant(0.0), None);
+}
+
+#[test] // Results are logical. The Hessian of a quadratic function is constant.
+fn test_hessian_matrix() {
+    let a = Array2::from_shape_fn((20, 21), |(x, y)| {
+        let (x, y) = (x as f64 - 10.0, y as f64 - 10.0);
+        1.5 * x * x - 2.0 * x * y + 0.25 * y * y
+    });
+    let hessian = hessian_matrix(&a, 1.0, &[1.0]);
+    assert_eq!(hessian.len(), 3);
+    let center = s![6..14, 6..15];
+    for (h, gt) in hessian.iter().zip([3.0, -2.0, 0.5]) {
+        assert_relative_eq!(h.slice(center), Array2::from_elem((8, 9), gt), epsilon = 1e-2);
+    }
+
+    // The eigenvalues of [[3, -2], [-2, 0.5]], in decreasing order
+    let eigenvalues = hessian_eigenvalues(&hessian);
+    let (e1, e2) = (1.75 + 5.5625f64.sqrt(), 1.75 - 5.5625f64.sqrt());
+    assert_relative_eq!(
+        eigenvalues[0].slice(center),
+        Array2::from_elem((8, 9), e1),
+        epsilon = 1e-2
+    );
+    assert_relative_eq!(
+        eigenvalues[1].slice(center),
+        Array2::from_elem((8, 9), e2),
+        epsilon = 1e-2
+    );
+
+    // With a spacing, the derivatives are in physical units
+    let a = Array3::from_shape_fn((12, 13, 14), |(x, _, _)| (x as f64 - 6.0).powi(2));
+    let hessian = hessian_matrix(&a, 2.0, &[2.0, 2.0, 1.0]);
+    assert_eq!(hessian.len(), 6);
+    let center = s![5..7, 5..8, 5..9];
+    for (h, gt) in hessian.iter().zip([0.5, 0.0, 0.0, 0.0, 0.0, 0.0]) {
+        assert_relative_eq!(h.slice(center), Array3::from_elem((2, 3, 4), gt), epsilon = 1e-2);
+    }
+    let eigenvalues = hessian_eigenvalues(&hessian);
+    assert_eq!(eigenvalues.len(), 3);
+    assert_relative_eq!(eigenvalues[0].slice(center), hessian[0].slice(center), epsilon = 1e-10);
+}
+
+#[test] // Results are logical. The ridge filters must respond on the ridges only.
+fn test_ridge_filters() {
+    // A dark line on a bright background
+    let mut a = Array2::from_elem((15, 15), 1.0);
+    a.column_mut(7).fill(0.0);
+    let sigmas = [1.0, 1.5];
+
+    let check = |filtered: Array2<f64>, inverted: Array2<f64>| {
+        assert!(filtered[(7, 7)] > 0.0);
+        assert!(filtered.iter().all(|&v| v >= 0.0));
+        assert!(filtered[(7, 7)] > 10.0 * filtered[(7, 2)]);
+        assert!(filtered[(7, 7)] > 10.0 * filtered[(7, 12)]);
+        assert!(filtered[(7, 7)] > 10.0 * inverted[(7, 7)]);
+    };
+    check(frangi(&a, &sigmas, 0.5, 0.5, None, true), frangi(&a, &sigmas, 0.5, 0.5, None, false));
+
+    // The default `gamma` is half of the maximal Hessian norm of the first scale, for all scales
+    let h = hessian_matrix(&a, sigmas[0], &[1.0]);
+    let norms = Zip::from(&h[0]).and(&h[1]).and(&h[2]).map_collect(|&xx, &xy, &yy| {
+        (xx * xx + 2.0 * xy * xy + yy * yy).sqrt() * sigmas[0].powi(2)
+    });
+    let gamma = norms.fold(0.0, |acc: f64, &v| acc.max(v)) / 2.0;
+    assert_relative_eq!(
+        frangi(&a, &sigmas, 0.5, 0.5, None, true),
+        frangi(&a, &sigmas, 0.5, 0.5, Some(gamma), true),
+        epsilon = 1e-10
+    );
+
+    check(sato(&a, &sigmas, true), sato(&a, &sigmas, false));
+    check(meijering(&a, &sigmas, None, true), meijering(&a, &sigmas, None, false));
+    assert_relative_eq!(meijering(&a, &sigmas, None, true)[(7, 7)], 1.0);
+
+    // A bright tube on a dark background
+    let mut a = Array3::from_elem((13, 13, 13), 0.0);
+    a.slice_mut(s![5..8, 5..8, ..]).fill(1.0);
+    let sigmas = [1.0, 2.0];
+    let check = |filtered: Array3<f64>| {
+        assert!(filtered[(6, 6, 6)] > 0.0);
+        assert!(filtered[(6, 6, 6)] > 10.0 * filtered[(1, 1, 6)]);
+        assert!(filtered[(6, 6, 6)] > 10.0 * filtered[(6, 11, 6)]);
+    };
+    check(frangi(&a, &sigmas, 0.5, 0.5, Some(0.25), false));
+    check(sato(&a, &sigmas, false));
+    check(meijering(&a, &sigmas, None, false));
+}
+
+#[test] // Results verified with scikit-image
+fn test_ridge_filters_values() {
+    // A half-period of cosine is its own mirror image, so scikit-image's responses are analytic.
+    // Only the bright half is checked. On the other half, scikit-image's Hessian has an exact zero
+    // eigenvalue, which makes its 2D Frangi response degenerate.
+    let a = Array2::from_shape_fn((16, 12), |(x, _)| {
+        (std::f64::consts::PI * (x as f64 + 0.5) / 16.0).cos()
+    });
+    let ridge = s![..8, 6];
+
+    let frangi_gt = arr1(&[
+        0.86466471, 0.84264264, 0.79209211, 0.70081207, 0.55634931, 0.36156856, 0.15647457,
+        0.01921412,
+    ]);
+    let filtered = frangi(&a, &[1.5], 0.5, 0.5, None, false);
+    assert_relative_eq!(filtered.slice(ridge), frangi_gt, epsilon = 1e-4);
+
+    let sato_gt = arr1(&[
+        0.08266271, 0.07948603, 0.07325475, 0.06420832, 0.05269441, 0.03915548, 0.02411182,
+        0.00814157,
+    ]);
+    let filtered = sato(&a, &[1.0, 1.5], false);
+    assert_relative_eq!(filtered.slice(ridge), sato_gt, max_relative = 1e-3);
+
+    let meijering_gt = arr1(&[
+        1.0, 0.96157056, 0.8861885, 0.77675072, 0.63746284, 0.47367762, 0.29168924, 0.0984914,
+    ]);
+    let filtered = meijering(&a, &[1.5], None, false);
+    assert_relative_eq!(filtered.slice(ridge), meijering_gt, epsilon = 1e-7);
+}
+
+#[test] // Results are logical. The gradient of a linear function is constant.
+fn test_structure_tensor() {
+    let a = Array2::from_shape_fn((30, 31), |(x, y)| 2.0 * x as f64 + y as f64);
+    let tensor = structure_tensor(&a, 1.0, 1.5, BorderMode::Nearest);
+    assert_eq!(tensor.len(), 3);
+    let center = s![12..18, 12..19];
+    for (t, gt) in tensor.iter().zip([4.0, 2.0, 1.0]) {
+        assert_relative_eq!(t.slice(center), Array2::from_elem((6, 7), gt), epsilon = 1e-2);
+    }
+
+    let (eigenvalues, principal) = structure_tensor_eigenvalues(&tensor);
+    assert_eq!(eigenvalues.len(), 2);
+    assert_eq!(principal.len(), 2);
+    assert_relative_eq!(
+        eigenvalues[0].slice(center),
+        Array2::from_elem((6, 7), 5.0),
+        epsilon = 1e-2
+    );
+    assert_relative_eq!(eigenvalues[1].slice(center), Array2::zeros((6, 7)), epsilon = 1e-2);
+    let (gx, gy) = (2.0 / 5.0f64.sqrt(), 1.0 / 5.0f64.sqrt());
+    assert_relative_eq!(principal[0].slice(center), Array2::from_elem((6, 7), gx), epsilon = 1e-6);
+    assert_relative_eq!(principal[1].slice(center), Array2::from_elem((6, 7), gy), epsilon = 1e-6);
+
+    // Stripes along the last axis. The orientation is the first axis, everywhere.
+    let a = Array3::from_shape_fn((10, 11, 12), |(x, _, _)| (x as f64 * 0.7).sin());
+    let tensor = structure_tensor(&a, 1.0, 2.0, BorderMode::Reflect);
+    assert_eq!(tensor.len(), 6);
+    let (eigenvalues, principal) = structure_tensor_eigenvalues(&tensor);
+    assert!(eigenvalues[1].iter().all(|&v| v.abs() < 1e-10));
+    assert!(eigenvalues[2].iter().all(|&v| v.abs() < 1e-10));
+    assert!(principal[0].iter().all(|&v| (v - 1.0).abs() < 1e-10));
+    assert!(principal[1].iter().all(|&v| v.abs() < 1e-10));
+    assert!(principal[2].iter().all(|&v| v.abs() < 1e-10));
+}
+
+#[test] // Results are logical.
+fn test_unsharp_mask() {
+    let a = Array2::from_shape_fn((12, 13), |(x, y)| if y < 6 { 2.0 } else { 6.0 } + x as f64);
+    assert_eq!(unsharp_mask(&a, 1.0, 0.0, 0.0, BorderMode::Reflect), a);
+
+    // The edge contrast is increased on both sides
+    let sharp = unsharp_mask(&a, 1.0, 1.5, 0.0, BorderMode::Reflect);
+    assert!(sharp[(5, 5)] < a[(5, 5)]);
+    assert!(sharp[(5, 6)] > a[(5, 6)]);
+    assert_relative_eq!(sharp[(5, 0)], a[(5, 0)], epsilon = 1e-6);
+
+    // The small details are ignored
+    let sharp = unsharp_mask(&a, 1.0, 1.5, 10.0, BorderMode::Reflect);
+    assert_eq!(sharp, a);
+
+    // Integer values are clipped to their range
+    let a = Array2::from_shape_fn((10, 10), |(_, y)| if y < 5 { 10u8 } else { 250 });
+    let sharp = unsharp_mask(&a, 1.0, 2.0, 0.0, BorderMode::Nearest);
+    assert_eq!(sharp[(3, 4)], 0);
+    assert_eq!(sharp[(3, 5)], 255);
+    assert_eq!(sharp[(3, 0)], 10);
+    assert_eq!(sharp[(3, 9)], 250);
+
+    // Even the 64-bit integers, whose bounds can't be represented exactly in `f64`
+    let a = Array2::from_shape_fn((10, 10), |(_, y)| if y < 5 { 10u64 } else { u64::MAX - 10 });
+    let sharp = unsharp_mask(&a, 1.0, 2.0, 0.0, BorderMode::Nearest);
+    assert_eq!(sharp[(3, 4)], 0);
+    assert_eq!(sharp[(3, 5)], u64::MAX);
+    let a = a.mapv(|v| if v == 10 { i64::MIN + 10 } else { i64::MAX - 10 });
+    let sharp = unsharp_mask(&a, 1.0, 2.0, 0.0, BorderMode::Nearest);
+    assert_eq!(sharp[(3, 4)], i64::MIN);
+    assert_eq!(sharp[(3, 5)], i64::MAX);
+}
+
+#[test] // Results are logical. The noise is removed and the edge of the guide is preserved.
+fn test_guided_filter() {
+    let guide = Array3::from_shape_fn((10, 11, 12), |(x, _, _)| if x < 5 { 0.0 } else { 1.0 });
+    let noisy = &guide * 4.0 + 1.0 + (noise(guide.dim()) - 0.5) * 0.2;
+    let filtered = guided_filter(&guide, &noisy, 2, 1e-4);
+    let clean = guide.mapv(|g| g * 4.0 + 1.0);
+    let error = |a: &Array3<f64>| (a - &clean).mapv(|v| v * v).mean().unwrap();
+    assert!(error(&filtered) < error(&noisy) / 4.0);
+    assert!(filtered[(4, 5, 6)] < 1.2);
+    assert!(filtered[(5, 5, 6)] > 4.8);
+
+    // With a large `eps`, the coefficients `b` are the box means, which are then averaged again
+    let box_filtered = uniform_filter(&noisy, 5, BorderMode::Reflect);
+    let box_filtered = uniform_filter(&box_filtered, 5, BorderMode::Reflect);
+    assert_relative_eq!(guided_filter(&guide, &noisy, 2, 1e8), box_filtered, epsilon = 1e-6);
+
+    // A linear transform of the guide is preserved
+    let a = Array2::from_shape_fn((9, 8), |(x, y)| (x * y) as f64 + 0.5 * x as f64);
+    assert_relative_eq!(
+        guided_filter(&a, &a.mapv(|v| 3.0 * v - 2.0), 1, 1e-10),
+        a.mapv(|v| 3.0 * v - 2.0),
+        epsilon = 1e-4
+    );
+}
+
+/// Brute force local histogram, for the `true` voxels of `footprint` inside the image.
+fn window_values(data: &Array3<u16>, footprint: &Array3<bool>, center: [usize; 3]) -> Vec<u16> {
+    let half = [0, 1, 2].map(|d| (footprint.shape()[d] / 2) as isize);
+    footprint
+        .indexed_iter()
+        .filter(|(_, &f)| f)
+        .filter_map(|((x, y, z), _)| {
+            let idx = [x, y, z];
+            let c = [0, 1, 2].map(|d| center[d] as isize + idx[d] as isize - half[d]);
+            (0..3)
+                .all(|d| c[d] >= 0 && c[d] < data.shape()[d] as isize)
+                .then(|| data[(c[0] as usize, c[1] as usize, c[2] as usize)])
+        })
+        .collect()
+}
+
+#[test] // Results verified with a brute force implementation.
+fn test_local_histogram() {
+    let data = noise((6, 7, 8)).mapv(|v| (v * 10.0) as u16);
+    let mut footprint = Array3::from_elem((3, 2, 5), false);
+    footprint.slice_mut(s![1, .., ..]).fill(true);
+    footprint.slice_mut(s![.., 1, 2]).fill(true);
+
+    let mut histograms = local_histogram(&data, &footprint);
+    let mut nb_voxels = 0;
+    for ((x, y, z), _) in data.indexed_iter() {
+        let mut values = window_values(&data, &footprint, [x, y, z]);
+        values.sort();
+        let histogram = histograms.next_histogram().unwrap();
+        assert_eq!(histogram.total(), values.len());
+        assert_eq!(histogram.rank(0), Some(values[0] as usize));
+        assert_eq!(histogram.rank(values.len() / 2), Some(values[values.len() / 2] as usize));
+        assert_eq!(histogram.rank(values.len()), None);
+        nb_voxels += 1;
+    }
+    assert_eq!(nb_voxels, data.len());
+    assert!(histograms.next_histogram().is_none());
+    assert!(histograms.next_histogram().is_none());
+}
+
+#[test] // Results verified with a brute force implementation.
+fn test_entropy_filter() {
+    let data = noise((6, 7, 8)).mapv(|v| (v * 10.0) as u16);
+    let footprint = Array3::from_elem((3, 3, 3), true);
+    let entropy = entropy_filter(&data, &footprint);
+    for ((x, y, z), &e) in entropy.indexed_iter() {
+        let values = window_values(&data, &footprint, [x, y, z]);
+        let n = values.len() as f64;
+        let expected = (0..10).fold(0.0, |acc, v| {
+            let p = values.iter().filter(|&&w| w == v).count() as f64 / n;
+            if p > 0.0 {
+                acc - p * p.log2()
+            } else {
+                acc
+            }
+        });
+        assert_relative_eq!(e, expected, epsilon = 1e-10);
+    }
+
+    let a = Array2::<u8>::from_elem((5, 6), 17);
+    assert_eq!(entropy_filter(&a, &Array2::from_elem((3, 3), true)), Array2::zeros((5, 6)));
+    let a = Array2::<u8>::from_shape_fn((6, 6), |(x, y)| ((x + y) % 2) as u8);
+    let entropy = entropy_filter(&a, &Array2::from_elem((2, 2), true));
+    assert_relative_eq!(entropy.slice(s![1.., 1..]), Array2::ones((5, 5)), epsilon = 1e-12);
+}
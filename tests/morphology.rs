@@ -1,9 +1,18 @@
-use ndarray::{s, Array3, ShapeBuilder};
+use ndarray::{arr1, arr2, s, Array1, Array2, Array3, ArrayD, Axis, IxDyn, ShapeBuilder};
 
 use ndarray_ndimage::{
-    binary_closing, binary_dilation, binary_erosion, binary_opening, Kernel3d, Mask,
+    binary_black_tophat, binary_closing, binary_dilation, binary_dilation_until_stable,
+    binary_erosion, binary_erosion_until_stable, binary_fill_holes, binary_fill_holes_slicewise,
+    binary_hit_or_miss, binary_morphological_gradient, binary_opening, binary_propagation,
+    binary_white_tophat, black_tophat, distance_transform_edt, grey_closing, grey_dilation,
+    grey_erosion, grey_opening, label, maximum_filter, medial_axis, minimum_filter,
+    morphological_gradient, morphological_laplace, reconstruction, skeleton_graph, skeletonize_3d,
+    white_tophat, BorderMode, Kernel3d, Mask, ReconstructionMethod,
 };
 
+mod common;
+use common::noise;
+
 #[test] // Results verified with the `binary_erosion` function from SciPy. (v1.9)
 fn test_binary_erosion() {
     let star = Kernel3d::Star.generate();
@@ -17,25 +26,25 @@ fn test_binary_erosion() {
     gt[(0, 2, 3)] = false;
     gt[(0, 3, 2)] = false;
     gt[(1, 2, 2)] = false;
-    assert_eq!(binary_erosion(&mask.view(), &star, 1), gt);
+    assert_eq!(binary_erosion(&mask.view(), &star, 1, None, true, &[0]), gt);
 
     let mut mask = Mask::from_elem((6, 7, 8), false);
     mask.slice_mut(s![1..5, 1..6, 1..7]).fill(true);
     let mut gt = Mask::from_elem((6, 7, 8), false);
     gt.slice_mut(s![2..4, 2..5, 2..6]).fill(true);
-    assert_eq!(binary_erosion(&mask, &star, 1), gt);
+    assert_eq!(binary_erosion(&mask, &star, 1, None, true, &[0]), gt);
 
     let mut mask = Mask::from_elem((7, 7, 7), false);
     mask.slice_mut(s![2.., 1.., 1..]).fill(true);
     let mut gt = Mask::from_elem((7, 7, 7), false);
     gt.slice_mut(s![4.., 3.., 3..]).fill(true);
-    assert_eq!(gt, binary_erosion(&mask.view(), &star, 2));
+    assert_eq!(gt, binary_erosion(&mask.view(), &star, 2, None, true, &[0]));
 
     let mut mask = Mask::from_elem((9, 9, 9), false);
     mask.slice_mut(s![2.., 1.., ..]).fill(true);
     let mut gt = Mask::from_elem((9, 9, 9), false);
     gt.slice_mut(s![5.., 4.., ..]).fill(true);
-    assert_eq!(gt, binary_erosion(&mask.view(), &star, 3));
+    assert_eq!(gt, binary_erosion(&mask.view(), &star, 3, None, true, &[0]));
 }
 
 #[test] // Results verified with the `binary_erosion` function from SciPy. (v1.9)
@@ -48,7 +57,7 @@ fn test_binary_erosion_hole() {
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![4..7, 4..7, 4..7]).assign(&!&star);
 
-    assert_eq!(gt, binary_erosion(&mask, &star, 1));
+    assert_eq!(gt, binary_erosion(&mask, &star, 1, None, true, &[0]));
 }
 
 #[test] // Results verified with the `binary_erosion` function from SciPy. (v1.9)
@@ -61,7 +70,7 @@ fn test_binary_erosion_ball_kernel() {
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![4..7, 4..7, 4..7]).assign(&!&ball);
 
-    assert_eq!(gt, binary_erosion(&mask, &ball, 1));
+    assert_eq!(gt, binary_erosion(&mask, &ball, 1, None, true, &[0]));
 }
 
 #[test] // Results verified with the `binary_erosion` function from SciPy. (v1.9)
@@ -75,21 +84,28 @@ fn test_binary_erosion_full_kernel() {
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![4..7, 4..7, 4..7]).fill(false);
 
-    assert_eq!(gt, binary_erosion(&mask, &Kernel3d::Full.generate(), 1));
+    assert_eq!(gt, binary_erosion(&mask, &Kernel3d::Full.generate(), 1, None, true, &[0]));
 
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![3..8, 3..8, 3..8]).fill(false);
-    assert_eq!(gt, binary_erosion(&mask, &kernel5, 1));
+    assert_eq!(gt, binary_erosion(&mask, &kernel5, 1, None, true, &[0]));
 
     let mut mask = Mask::from_elem((11, 11, 11), true);
     mask[(10, 10, 10)] = false;
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![6.., 6.., 6..]).fill(false);
-    assert_eq!(gt, binary_erosion(&mask, &kernel5, 2));
+    assert_eq!(gt, binary_erosion(&mask, &kernel5, 2, None, true, &[0]));
 
     let mask = Mask::from_elem((13, 13, 13), true);
     let gt = Mask::from_elem((13, 13, 13), true);
-    assert_eq!(gt, binary_erosion(&mask, &kernel5, 3));
+    assert_eq!(gt, binary_erosion(&mask, &kernel5, 3, None, true, &[0]));
+
+    // An axis shorter than the kernel
+    let mut mask = Mask::from_elem((1, 6, 6), true);
+    mask[(0, 2, 2)] = false;
+    let mut gt = Mask::from_elem((1, 6, 6), true);
+    gt.slice_mut(s![.., 1..4, 1..4]).fill(false);
+    assert_eq!(gt, binary_erosion(&mask, &Kernel3d::Full.generate(), 1, None, true, &[0]));
 }
 
 #[test] // Results verified with the `binary_dilation` function from SciPy. (v1.9)
@@ -125,44 +141,44 @@ fn test_binary_dilation_plain() {
     gt.slice_mut(s![2..w - 1, 2..h - 1, 1..d]).fill(true);
     gt.slice_mut(s![2..w - 1, h - 1, 2..d - 1]).fill(true);
 
-    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Star.generate(), 1));
+    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Star.generate(), 1, None, false, &[0]));
 
     let mut mask = Mask::from_elem((w, h, d), false);
     mask.slice_mut(s![4, 4, 4..]).fill(true);
     let mut gt = Mask::from_elem((w, h, d), false);
     gt.slice_mut(s![3..6, 3..6, 3..]).fill(true);
     gt.slice_mut(s![3..6; 2, 3..6; 2, 3]).fill(false);
-    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Ball.generate(), 1));
+    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Ball.generate(), 1, None, false, &[0]));
 
     let mut mask = Mask::from_elem((w, h, d), false);
     mask[(4, 4, 4)] = true;
     let mut gt = Mask::from_elem((w, h, d), false);
     gt.slice_mut(s![2.., 2.., 2..]).fill(true);
-    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Full.generate(), 2));
+    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Full.generate(), 2, None, false, &[0]));
 
     let mut mask = Mask::from_elem((w, h, d), false);
     mask[(4, 5, 5)] = true;
     let mut gt = Mask::from_elem((w, h, d), false);
     gt.slice_mut(s![1.., 2.., 2..]).fill(true);
-    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Full.generate(), 3));
+    assert_eq!(gt, binary_dilation(&mask.view(), &Kernel3d::Full.generate(), 3, None, false, &[0]));
 
     let mut mask = Mask::from_elem((w, h, d), false);
     mask[(3, 4, 5)] = true;
     let mut gt = Mask::from_elem((w, h, d), false);
     gt.slice_mut(s![1..6, 2.., 3..]).fill(true);
-    assert_eq!(gt, binary_dilation(&mask, &kernel5, 1));
+    assert_eq!(gt, binary_dilation(&mask, &kernel5, 1, None, false, &[0]));
 
     let mut mask = Mask::from_elem((9, 9, 9), false);
     mask[(3, 4, 5)] = true;
     let mut gt = Mask::from_elem((9, 9, 9), false);
     gt.slice_mut(s![..8, .., 1..]).fill(true);
-    assert_eq!(gt, binary_dilation(&mask, &kernel5, 2));
+    assert_eq!(gt, binary_dilation(&mask, &kernel5, 2, None, false, &[0]));
 
     let mut mask = Mask::from_elem((11, 11, 11), false);
     mask[(3, 4, 5)] = true;
     let mut gt = Mask::from_elem((11, 11, 11), false);
     gt.slice_mut(s![..10, .., ..]).fill(true);
-    assert_eq!(gt, binary_dilation(&mask, &kernel5, 3));
+    assert_eq!(gt, binary_dilation(&mask, &kernel5, 3, None, false, &[0]));
 }
 
 #[test] // Results verified with the `binary_dilation` function from SciPy. (v1.9)
@@ -173,7 +189,7 @@ fn test_binary_dilation_corner() {
     let mut gt = Mask::from_elem((11, 11, 11), true);
     gt.slice_mut(s![8.., 8.., 8..]).fill(false);
 
-    assert_eq!(gt, binary_dilation(&mask, &Kernel3d::Full.generate(), 1));
+    assert_eq!(gt, binary_dilation(&mask, &Kernel3d::Full.generate(), 1, None, false, &[0]));
 }
 
 #[test] // Results verified with the `binary_dilation` function from SciPy. (v1.9)
@@ -235,14 +251,14 @@ fn test_asymmetric_kernel() {
     let mut gt = Mask::from_elem(mask.dim(), false);
     gt.slice_mut(s![0..3, 1..4, 1..4]).assign(&star);
     gt[(0, 2, 1)] = true;
-    assert_eq!(binary_dilation(&mask.view(), &star, 1), gt);
+    assert_eq!(binary_dilation(&mask.view(), &star, 1, None, false, &[0]), gt);
 
     let mut star = Kernel3d::Star.generate();
     star[(1, 0, 2)] = true;
     let mut gt = Mask::from_elem(mask.dim(), false);
     gt.slice_mut(s![0..3, 1..4, 1..4]).assign(&star);
     gt[(1, 1, 3)] = true;
-    assert_eq!(binary_dilation(&mask.view(), &star, 1), gt);
+    assert_eq!(binary_dilation(&mask.view(), &star, 1, None, false, &[0]), gt);
 
     let mut mask = Mask::from_elem((4, 5, 6), true);
     mask[(2, 2, 1)] = false;
@@ -252,14 +268,14 @@ fn test_asymmetric_kernel() {
     let mut gt = Mask::from_elem(mask.dim(), true);
     gt.slice_mut(s![1..4, 1..4, 0..3]).assign(&!Kernel3d::Star.generate());
     gt[(3, 2, 2)] = false;
-    assert_eq!(binary_erosion(&mask.view(), &star, 1), gt);
+    assert_eq!(binary_erosion(&mask.view(), &star, 1, None, true, &[0]), gt);
 
     let mut star = Kernel3d::Star.generate();
     star[(1, 0, 2)] = true;
     let mut gt = Mask::from_elem(mask.dim(), true);
     gt.slice_mut(s![1..4, 1..4, 0..3]).assign(&!Kernel3d::Star.generate());
     gt[(2, 3, 0)] = false;
-    assert_eq!(binary_erosion(&mask.view(), &star, 1), gt);
+    assert_eq!(binary_erosion(&mask.view(), &star, 1, None, true, &[0]), gt);
 }
 
 #[test] // Results are logical. Both orders should always give the same results.
@@ -267,10 +283,10 @@ fn test_memory_order() {
     let mut star = Kernel3d::Star.generate();
     let test_owned = |dim: (usize, usize, usize), kernel: &Array3<bool>, iterations: usize| {
         let test = Array3::from_elem(dim, true);
-        let c = binary_erosion(&test, &kernel, 1);
+        let c = binary_erosion(&test, &kernel, 1, None, true, &[0]);
         let mut test_f = Array3::from_elem(test.dim().f(), true);
         test_f.assign(&test);
-        let f = binary_erosion(&test_f, &kernel, iterations);
+        let f = binary_erosion(&test_f, &kernel, iterations, None, true, &[0]);
         assert_eq!(c, f);
     };
     test_owned((4, 5, 6), &star, 1);
@@ -309,13 +325,788 @@ fn test_memory_order() {
     let kernel_view = kernel.slice(s![..;2, ..;2, ..;2]);
     let test_view = |dim: (usize, usize, usize)| {
         let test = Array3::from_elem(dim, true);
-        let c = binary_erosion(&test, &kernel_view, 1);
+        let c = binary_erosion(&test, &kernel_view, 1, None, true, &[0]);
         let mut test_f = Array3::from_elem(test.dim().f(), true);
         test_f.assign(&test);
-        let f = binary_erosion(&test_f, &kernel_view, 1);
+        let f = binary_erosion(&test_f, &kernel_view, 1, None, true, &[0]);
         assert_eq!(c, f);
     };
     test_view((4, 5, 6));
     test_view((5, 5, 5));
     test_view((6, 5, 4));
 }
+
+#[test] // Results are logical. A flat cubic footprint is a minimum/maximum filter.
+fn test_grey_erosion_dilation() {
+    let data = noise((6, 7, 8)) - 0.5;
+    let footprint = Array3::from_elem((3, 3, 3), true);
+    for mode in [BorderMode::Reflect, BorderMode::Nearest, BorderMode::Constant(0.1)] {
+        let eroded = grey_erosion(&data, &footprint, None, mode);
+        assert_eq!(eroded, minimum_filter(&data, 3, mode, 0));
+        let dilated = grey_dilation(&data, &footprint, None, mode);
+        assert_eq!(dilated, maximum_filter(&data, 3, mode, 0));
+    }
+
+    // Asymmetric footprint. The dilation uses the reflected footprint.
+    let data = arr1(&[0, 0, 0, 5, 0, 0, 0]);
+    let footprint = arr1(&[true, true, false]);
+    let mode = BorderMode::Constant(0);
+    assert_eq!(grey_dilation(&data, &footprint, None, mode), arr1(&[0, 0, 5, 5, 0, 0, 0]));
+    let data = arr1(&[5, 5, 5, 0, 5, 5, 5]);
+    let mode = BorderMode::Nearest;
+    assert_eq!(grey_erosion(&data, &footprint, None, mode), arr1(&[5, 5, 5, 0, 0, 5, 5]));
+
+    // Non-flat structuring element
+    let data = arr2(&[[0.0, 0.0, 0.0, 0.0], [0.0, 4.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0]]);
+    let footprint = Array2::from_elem((3, 3), true);
+    let structure = arr2(&[[0.0, 1.0, 0.0], [1.0, 2.0, 1.0], [0.0, 1.0, 0.0]]);
+    let mode = BorderMode::Constant(0.0);
+    let dilated = grey_dilation(&data, &footprint, Some(&structure), mode);
+    let gt = arr2(&[[4.0, 5.0, 4.0, 2.0], [5.0, 6.0, 5.0, 2.0], [4.0, 5.0, 4.0, 2.0]]);
+    assert_eq!(dilated, gt);
+    let eroded = grey_erosion(&data, &footprint, Some(&structure), mode);
+    assert_eq!(eroded[(1, 1)], -1.0);
+    assert_eq!(eroded[(0, 3)], -2.0);
+
+    // Integer types saturate
+    let data = arr2(&[[250u8, 250, 250], [250, 10, 250], [250, 250, 250]]);
+    let structure = arr2(&[[0, 20, 0], [20, 20, 20], [0, 20, 0]]);
+    let dilated = grey_dilation(&data, &footprint, Some(&structure), BorderMode::Nearest);
+    assert_eq!(dilated, Array2::from_elem((3, 3), 255));
+    let eroded = grey_erosion(&data, &footprint, Some(&structure), BorderMode::Nearest);
+    assert_eq!(eroded, arr2(&[[10, 0, 10], [0, 0, 0], [10, 0, 10]]));
+    let data = arr1(&[120i8, -120]);
+    let structure = arr1(&[-50, 0, 50]);
+    let footprint = arr1(&[true; 3]);
+    let dilated = grey_dilation(&data, &footprint, Some(&structure), BorderMode::Nearest);
+    assert_eq!(dilated, arr1(&[127, 127]));
+    let eroded = grey_erosion(&data, &footprint, Some(&structure), BorderMode::Nearest);
+    assert_eq!(eroded, arr1(&[-128, -128]));
+}
+
+#[test] // Results are logical.
+fn test_grey_opening_closing() {
+    // The opening removes the bright details smaller than the footprint, the closing removes the
+    // dark ones. The larger structures are preserved.
+    let mut data = Array2::from_elem((12, 12), 10.0);
+    data.slice_mut(s![6.., 6..]).fill(50.0);
+    data[(2, 2)] = 90.0;
+    data[(8, 8)] = 0.0;
+    let footprint = Array2::from_elem((3, 3), true);
+    let mode = BorderMode::Reflect;
+
+    let opened = grey_opening(&data, &footprint, None, mode);
+    assert_eq!(opened[(2, 2)], 10.0);
+    assert_eq!(opened[(8, 8)], 0.0);
+    let mut gt = data.clone();
+    gt[(2, 2)] = 10.0;
+    assert_eq!(opened.slice(s![..6, ..]), gt.slice(s![..6, ..]));
+
+    let closed = grey_closing(&data, &footprint, None, mode);
+    assert_eq!(closed[(8, 8)], 50.0);
+    assert_eq!(closed[(2, 2)], 90.0);
+
+    // Anti-extensive and extensive
+    let data = (noise(50) * 1000.0).mapv(f64::floor);
+    let footprint = Array1::from_elem(5, true);
+    let opened = grey_opening(&data, &footprint, None, mode);
+    let closed = grey_closing(&data, &footprint, None, mode);
+    assert!(opened.iter().zip(&data).all(|(o, d)| o <= d));
+    assert!(closed.iter().zip(&data).all(|(c, d)| c >= d));
+}
+
+#[test] // Results are logical.
+fn test_tophat() {
+    // A small bright spot and a small dark spot on a slowly varying background
+    let mut data = Array2::from_shape_fn((15, 15), |(x, y)| (x + y) as f64);
+    data[(4, 4)] += 20.0;
+    data[(10, 10)] -= 20.0;
+    let footprint = Array2::from_elem((3, 3), true);
+    let mode = BorderMode::Reflect;
+
+    let white = white_tophat(&data, &footprint, None, mode);
+    assert!(white.iter().all(|&v| v >= 0.0));
+    assert!(white[(4, 4)] > 18.0);
+    assert_eq!(white[(10, 10)], 0.0);
+    assert_eq!(white.iter().filter(|&&v| v > 2.0).count(), 1);
+
+    let black = black_tophat(&data, &footprint, None, mode);
+    assert!(black.iter().all(|&v| v >= 0.0));
+    assert!(black[(10, 10)] > 18.0);
+    assert_eq!(black[(4, 4)], 0.0);
+    assert_eq!(black.iter().filter(|&&v| v > 2.0).count(), 1);
+
+    // With an asymmetric footprint, the closing can be smaller than the image near the borders
+    let data = arr1(&[3u8, 251, 224, 57, 95, 129, 116]);
+    let footprint = arr1(&[false, false, true, false, true]);
+    let black = black_tophat(&data, &footprint, None, BorderMode::Nearest);
+    assert_eq!(black, arr1(&[0.0, 0.0, 0.0, 72.0, 21.0, -13.0, 0.0]));
+
+    // Binary
+    let full = Kernel3d::Full.generate();
+    let mut mask = Mask::from_elem((9, 11, 11), false);
+    mask.slice_mut(s![2..7, 2..7, 2..7]).fill(true);
+    mask[(4, 4, 7)] = true;
+    let mut gt = Mask::from_elem(mask.dim(), false);
+    gt[(4, 4, 7)] = true;
+    assert_eq!(binary_white_tophat(&mask, &full, 1), gt);
+
+    mask[(4, 4, 7)] = false;
+    mask[(4, 4, 4)] = false;
+    let mut gt = Mask::from_elem(mask.dim(), false);
+    gt[(4, 4, 4)] = true;
+    assert_eq!(binary_black_tophat(&mask, &full, 1), gt);
+}
+
+#[test] // Results are logical.
+fn test_morphological_gradient_laplace() {
+    let mut data = Array2::from_elem((8, 8), 1);
+    data.slice_mut(s![.., 4..]).fill(5);
+    let footprint = Array2::from_elem((3, 3), true);
+    let mode = BorderMode::Nearest;
+
+    let gradient = morphological_gradient(&data, &footprint, None, mode);
+    let mut gt = Array2::zeros((8, 8));
+    gt.slice_mut(s![.., 3..5]).fill(4.0);
+    assert_eq!(gradient, gt);
+
+    let laplace = morphological_laplace(&data, &footprint, None, mode);
+    let mut gt = Array2::zeros((8, 8));
+    gt.slice_mut(s![.., 3]).fill(4.0);
+    gt.slice_mut(s![.., 4]).fill(-4.0);
+    assert_eq!(laplace, gt);
+
+    // Unsigned types can have negative results
+    let laplace = morphological_laplace(&Array2::from_elem((4, 5), 250u8), &footprint, None, mode);
+    assert_eq!(laplace, Array2::zeros((4, 5)));
+    let mut data = Array2::from_elem((5, 5), 200u8);
+    data[(2, 2)] = 0;
+    let laplace = morphological_laplace(&data, &footprint, None, mode);
+    assert_eq!(laplace[(2, 2)], 200.0);
+    assert_eq!(laplace[(1, 1)], -200.0);
+    let footprint = arr1(&[true, false, false]);
+    let gradient =
+        morphological_gradient(&arr1(&[20u8, 10, 0]), &footprint, None, BorderMode::Nearest);
+    assert_eq!(gradient, arr1(&[-10.0, -20.0, -10.0]));
+
+    // Binary
+    let mut mask = Mask::from_elem((7, 7, 7), false);
+    mask.slice_mut(s![2..5, 2..5, 2..5]).fill(true);
+    let gradient = binary_morphological_gradient(&mask, &Kernel3d::Full.generate());
+    let mut gt = Mask::from_elem(mask.dim(), true);
+    gt.slice_mut(s![..1, .., ..]).fill(false);
+    gt.slice_mut(s![6.., .., ..]).fill(false);
+    gt.slice_mut(s![.., ..1, ..]).fill(false);
+    gt.slice_mut(s![.., 6.., ..]).fill(false);
+    gt.slice_mut(s![.., .., ..1]).fill(false);
+    gt.slice_mut(s![.., .., 6..]).fill(false);
+    gt[(3, 3, 3)] = false;
+    assert_eq!(gradient, gt);
+}
+
+#[test] // Results are logical. The binary operations are the greyscale operations on 0 and 1.
+fn test_binary_nd() {
+    let check = |mask: ArrayD<bool>, kernel: ArrayD<bool>, iterations: usize| {
+        let data = mask.mapv(|m| m as u8);
+        let mut mask_f = ArrayD::from_elem(IxDyn(mask.shape()).f(), false);
+        mask_f.assign(&mask);
+        for border in [false, true] {
+            let mode = BorderMode::Constant(border as u8);
+            let (mut eroded, mut dilated) = (data.clone(), data.clone());
+            for _ in 0..iterations {
+                eroded = grey_erosion(&eroded, &kernel, None, mode);
+                dilated = grey_dilation(&dilated, &kernel, None, mode);
+            }
+            let (eroded, dilated) = (eroded.mapv(|v| v == 1), dilated.mapv(|v| v == 1));
+            assert_eq!(binary_erosion(&mask, &kernel, iterations, None, border, &[0]), eroded);
+            assert_eq!(binary_dilation(&mask, &kernel, iterations, None, border, &[0]), dilated);
+
+            // Same results in Fortran order
+            assert_eq!(binary_erosion(&mask_f, &kernel, iterations, None, border, &[0]), eroded);
+            assert_eq!(binary_dilation(&mask_f, &kernel, iterations, None, border, &[0]), dilated);
+        }
+    };
+    let random_mask = |shape: &[usize], threshold: f64| noise(IxDyn(shape)).mapv(|v| v < threshold);
+
+    // 2D
+    let cross = arr2(&[[false, true, false], [true, true, true], [false, true, false]]).into_dyn();
+    check(random_mask(&[9, 12], 0.6), cross.clone(), 1);
+    check(random_mask(&[9, 12], 0.4), cross.clone(), 2);
+    let mut asymmetric = cross.clone();
+    asymmetric[[0, 0]] = true;
+    check(random_mask(&[10, 7], 0.7), asymmetric.clone(), 1);
+    check(random_mask(&[10, 7], 0.5), asymmetric.clone(), 3);
+    check(random_mask(&[10, 7], 0.3), ArrayD::from_elem(IxDyn(&[5, 3]), true), 2);
+
+    // Axes shorter than the kernel
+    check(random_mask(&[1, 9], 0.6), cross.clone(), 1);
+    check(random_mask(&[2, 9], 0.6), asymmetric.clone(), 1);
+    check(random_mask(&[3, 4], 0.7), ArrayD::from_elem(IxDyn(&[5, 5]), true), 1);
+    check(random_mask(&[8, 3], 0.7), ArrayD::from_elem(IxDyn(&[3, 7]), true), 2);
+
+    // 4D
+    let mut kernel = ArrayD::from_elem(IxDyn(&[3, 3, 3, 3]), false);
+    kernel[[1, 1, 1, 1]] = true;
+    for d in 0..4 {
+        for i in [0, 2] {
+            let mut idx = [1; 4];
+            idx[d] = i;
+            kernel[idx] = true;
+        }
+    }
+    check(random_mask(&[5, 6, 4, 7], 0.7), kernel.clone(), 1);
+    check(random_mask(&[5, 6, 4, 7], 0.2), kernel.clone(), 2);
+    kernel[[0, 0, 1, 2]] = true;
+    check(random_mask(&[4, 5, 6, 5], 0.6), kernel, 1);
+}
+
+#[test] // Results are logical.
+fn test_binary_mask_origin() {
+    let data = noise((12, 10)).mapv(|v| v < 0.5);
+    let mut kernel = Array2::from_elem((3, 3), false);
+    kernel.slice_mut(s![1, ..]).fill(true);
+    kernel.slice_mut(s![.., 1]).fill(true);
+    kernel[(0, 0)] = true;
+
+    // The voxels outside of the mask are never modified. The same result is obtained by applying
+    // one iteration at a time.
+    let mask = Array2::from_shape_fn(data.dim(), |(x, y)| (x + 2 * y) % 5 != 0);
+    for border in [false, true] {
+        let (mut eroded, mut dilated) = (data.clone(), data.clone());
+        for _ in 0..3 {
+            eroded = binary_erosion(&eroded, &kernel, 1, Some(&mask), border, &[0]);
+            dilated = binary_dilation(&dilated, &kernel, 1, Some(&mask), border, &[0]);
+        }
+        assert_eq!(binary_erosion(&data, &kernel, 3, Some(&mask), border, &[0]), eroded);
+        assert_eq!(binary_dilation(&data, &kernel, 3, Some(&mask), border, &[0]), dilated);
+        for ((&d, &m), (&e, &di)) in data.iter().zip(&mask).zip(eroded.iter().zip(&dilated)) {
+            if !m {
+                assert_eq!(d, e);
+                assert_eq!(d, di);
+            }
+        }
+    }
+
+    // Shifting a kernel is the same as padding it with `false` values
+    let kernel = Array2::from_elem((3, 3), true);
+    let mut padded = Array2::from_elem((3, 5), false);
+    padded.slice_mut(s![.., ..3]).assign(&kernel);
+    for border in [false, true] {
+        for iterations in [1, 2] {
+            assert_eq!(
+                binary_erosion(&data, &kernel, iterations, None, border, &[0, 1]),
+                binary_erosion(&data, &padded, iterations, None, border, &[0])
+            );
+            assert_eq!(
+                binary_dilation(&data, &kernel, iterations, None, border, &[0, 1]),
+                binary_dilation(&data, &padded, iterations, None, border, &[0])
+            );
+        }
+    }
+
+    // The erosion border
+    let data = Array2::from_elem((5, 5), true);
+    let mut gt = Array2::from_elem((5, 5), false);
+    gt.slice_mut(s![1..4, 1..4]).fill(true);
+    assert_eq!(binary_erosion(&data, &kernel, 1, None, false, &[0]), gt);
+    assert_eq!(binary_erosion(&data, &kernel, 1, None, true, &[0]), data);
+}
+
+#[test] // Results are logical. Compared with a brute-force implementation.
+fn test_binary_origin_short_axes() {
+    // The neighbor of `idx` at the kernel index `k` is at `idx + k - center` for the erosion and
+    // at `idx - k + center` for the dilation.
+    let brute_force = |data: &ArrayD<bool>,
+                       kernel: &ArrayD<bool>,
+                       origin: &[isize],
+                       border: bool,
+                       is_dilate: bool| {
+        let centers: Vec<_> =
+            kernel.shape().iter().zip(origin).map(|(&l, &o)| (l / 2) as isize + o).collect();
+        ArrayD::from_shape_fn(data.raw_dim(), |idx| {
+            let mut neighbors = kernel.indexed_iter().filter(|&(_, &k)| k).map(|(k, _)| {
+                let j: Vec<_> = (0..data.ndim())
+                    .map(|d| {
+                        let shift = k[d] as isize - centers[d];
+                        idx[d] as isize + if is_dilate { -shift } else { shift }
+                    })
+                    .collect();
+                let is_in = j.iter().zip(data.shape()).all(|(&j, &s)| (0..s as isize).contains(&j));
+                if is_in {
+                    data[j.iter().map(|&j| j as usize).collect::<Vec<_>>().as_slice()]
+                } else {
+                    border
+                }
+            });
+            if is_dilate {
+                data[&idx] || neighbors.any(|n| n)
+            } else {
+                data[&idx] && neighbors.all(|n| n)
+            }
+        })
+    };
+    let check = |data: &ArrayD<bool>, kernel: &ArrayD<bool>, origin: &[isize]| {
+        for border in [false, true] {
+            let (mut eroded, mut dilated) = (data.clone(), data.clone());
+            for iterations in 1..3 {
+                eroded = brute_force(&eroded, kernel, origin, border, false);
+                dilated = brute_force(&dilated, kernel, origin, border, true);
+                assert_eq!(binary_erosion(data, kernel, iterations, None, border, origin), eroded);
+                assert_eq!(
+                    binary_dilation(data, kernel, iterations, None, border, origin),
+                    dilated
+                );
+            }
+        }
+    };
+    // All origins of all 1D kernels on all 1D images, even when the image is shorter
+    for kernel_len in 1..5 {
+        let min_origin = -((kernel_len / 2) as isize);
+        for origin in min_origin..kernel_len as isize + min_origin {
+            let center = (origin - min_origin) as usize;
+            for k in 0..1 << kernel_len {
+                let kernel = ArrayD::from_shape_fn(IxDyn(&[kernel_len]), |idx| {
+                    idx[0] == center || k >> idx[0] & 1 == 1
+                });
+                for len in 1..6 {
+                    for v in 0..1 << len {
+                        let data = ArrayD::from_shape_fn(IxDyn(&[len]), |idx| v >> idx[0] & 1 == 1);
+                        check(&data, &kernel, &[origin]);
+                    }
+                }
+            }
+        }
+    }
+
+    // 2D, with short axes and asymmetric kernels
+    let kernel =
+        arr2(&[[true, true, false, true], [false, true, true, false], [true, false, true, true]])
+            .into_dyn();
+    for shape in [[1, 6], [2, 5], [3, 3], [6, 2], [7, 8]] {
+        let data = noise(IxDyn(&shape)).mapv(|v| v < 0.6);
+        for origin in [[-1, -2], [-1, 0], [0, -1], [0, 1], [1, -2], [1, 1]] {
+            check(&data, &kernel, &origin);
+        }
+    }
+}
+
+#[test] // Results are logical.
+fn test_binary_until_stable() {
+    let cross = arr2(&[[false, true, false], [true, true, true], [false, true, false]]);
+
+    // The square is peeled one layer at a time
+    let data = Array2::from_elem((9, 9), true);
+    let (eroded, nb_iterations) = binary_erosion_until_stable(&data, &cross, None, false, &[0]);
+    assert_eq!(nb_iterations, 5);
+    assert!(eroded.iter().all(|&e| !e));
+    assert_eq!(binary_erosion(&data, &cross, 5, None, false, &[0]), eroded);
+    assert_eq!(binary_erosion_until_stable(&data, &cross, None, true, &[0]), (data.clone(), 0));
+
+    // The number of iterations is the largest distance to the seed
+    let mut data = Array2::from_elem((7, 5), false);
+    data[(0, 0)] = true;
+    let (dilated, nb_iterations) = binary_dilation_until_stable(&data, &cross, None, false, &[0]);
+    assert_eq!(nb_iterations, 10);
+    assert!(dilated.iter().all(|&d| d));
+    assert_eq!(binary_dilation(&data, &cross, 10, None, false, &[0]), dilated);
+    assert_ne!(binary_dilation(&data, &cross, 9, None, false, &[0]), dilated);
+
+    // Propagation along a path
+    let mask = Array2::from_shape_fn((7, 12), |(x, y)| x == 2 && y < 10 || y == 9 && x >= 2);
+    let mut data = Array2::from_elem(mask.dim(), false);
+    data[(2, 0)] = true;
+    let (dilated, nb_iterations) =
+        binary_dilation_until_stable(&data, &cross, Some(&mask), false, &[0]);
+    assert_eq!(dilated, mask);
+    assert_eq!(nb_iterations, 9 + 4);
+}
+
+#[should_panic]
+#[test]
+fn test_binary_erosion_zero_iterations() {
+    let data = Array2::from_elem((5, 5), true);
+    let _ = binary_erosion(&data, &Array2::from_elem((3, 3), true), 0, None, false, &[0]);
+}
+
+#[test] // Results are logical.
+fn test_binary_fill_holes() {
+    let cross = arr2(&[[false, true, false], [true, true, true], [false, true, false]]);
+    let full = Array2::from_elem((3, 3), true);
+
+    // A ring is filled
+    let mut mask = Array2::from_elem((8, 9), false);
+    mask.slice_mut(s![1..6, 2..7]).fill(true);
+    let gt = mask.clone();
+    mask.slice_mut(s![2..5, 3..6]).fill(false);
+    assert_eq!(binary_fill_holes(&mask, &cross), gt);
+
+    // The hole is open on the border
+    let mut open = mask.clone();
+    open.slice_mut(s![..3, 4]).fill(false);
+    assert_eq!(binary_fill_holes(&open, &cross), open);
+
+    // The hole is only connected to the outside by a corner
+    let mut corner = mask.clone();
+    corner[(1, 2)] = false;
+    corner[(2, 3)] = false;
+    corner[(3, 3)] = true;
+    corner[(2, 4)] = true;
+    let mut gt = corner.clone();
+    gt.slice_mut(s![2..5, 3..6]).fill(true);
+    assert_eq!(binary_fill_holes(&corner, &cross), gt);
+    assert_eq!(binary_fill_holes(&corner, &full), corner);
+
+    // A tube is open in 3D, but its slices are closed
+    let mut tube = Array3::from_elem((4, 7, 7), false);
+    tube.slice_mut(s![.., 1..6, 1..6]).fill(true);
+    let gt = tube.clone();
+    tube.slice_mut(s![.., 2..5, 2..5]).fill(false);
+    assert_eq!(binary_fill_holes(&tube, &Kernel3d::Star.generate()), tube);
+    assert_eq!(binary_fill_holes_slicewise(&tube, &cross, Axis(0)), gt);
+    assert_eq!(binary_fill_holes_slicewise(&tube, &cross, Axis(1)), tube);
+}
+
+#[test] // Results are logical.
+fn test_binary_propagation() {
+    let cross = arr2(&[[false, true, false], [true, true, true], [false, true, false]]);
+    let full = Array2::from_elem((3, 3), true);
+
+    // Two objects touching by a corner
+    let mut mask = Array2::from_elem((7, 8), false);
+    mask.slice_mut(s![1..3, 1..4]).fill(true);
+    mask.slice_mut(s![3..6, 4..7]).fill(true);
+    let mut seed = Array2::from_elem((7, 8), false);
+    seed[(1, 1)] = true;
+
+    let mut gt = Array2::from_elem((7, 8), false);
+    gt.slice_mut(s![1..3, 1..4]).fill(true);
+    assert_eq!(binary_propagation(&seed, &mask, &cross, false), gt);
+    assert_eq!(binary_propagation(&seed, &mask, &full, false), mask);
+
+    // The seeds outside of the mask are kept, but don't propagate
+    seed[(0, 7)] = true;
+    gt[(0, 7)] = true;
+    assert_eq!(binary_propagation(&seed, &mask, &cross, false), gt);
+
+    // The border propagates into the objects touching it
+    let mut border = Array2::from_elem((7, 8), false);
+    border.slice_mut(s![4..7, ..2]).fill(true);
+    let mask = &mask | &border;
+    let empty = Array2::from_elem((7, 8), false);
+    assert_eq!(binary_propagation(&empty, &mask, &cross, false), empty);
+    assert_eq!(binary_propagation(&empty, &mask, &cross, true), border);
+}
+
+#[test] // Results are logical.
+fn test_reconstruction() {
+    let cross = arr2(&[[false, true, false], [true, true, true], [false, true, false]]);
+    let full = Array2::from_elem((3, 3), true);
+    let mask = noise((9, 11)).mapv(|v| (v * 1000.0) as i32);
+
+    // Compare with the slow definition: dilate the seed under the mask until stable
+    let slow = |seed: &Array2<i32>, footprint: &Array2<bool>, is_dilation: bool| {
+        let mut current = seed.clone();
+        loop {
+            let next = if is_dilation {
+                let dilated = grey_dilation(&current, footprint, None, BorderMode::Nearest);
+                ndarray::Zip::from(&dilated).and(&mask).map_collect(|&d, &m| d.min(m))
+            } else {
+                let eroded = grey_erosion(&current, footprint, None, BorderMode::Nearest);
+                ndarray::Zip::from(&eroded).and(&mask).map_collect(|&e, &m| e.max(m))
+            };
+            if next == current {
+                return current;
+            }
+            current = next;
+        }
+    };
+    for footprint in [&cross, &full] {
+        let seed = mask.mapv(|m| (m - 400).max(0));
+        let reconstructed = reconstruction(&seed, &mask, footprint, ReconstructionMethod::Dilation);
+        assert_eq!(reconstructed, slow(&seed, footprint, true));
+
+        let seed = mask.mapv(|m| (m + 400).min(999));
+        let reconstructed = reconstruction(&seed, &mask, footprint, ReconstructionMethod::Erosion);
+        assert_eq!(reconstructed, slow(&seed, footprint, false));
+    }
+
+    // A single seed reconstructs the whole plateau, but not the higher peak nearby
+    let mask = arr2(&[[1, 1, 1, 0, 0], [1, 5, 1, 0, 9], [1, 1, 1, 0, 0]]);
+    let mut seed = Array2::zeros((3, 5));
+    seed[(0, 0)] = 1;
+    let gt = arr2(&[[1, 1, 1, 0, 0], [1, 1, 1, 0, 0], [1, 1, 1, 0, 0]]);
+    assert_eq!(reconstruction(&seed, &mask, &full, ReconstructionMethod::Dilation), gt);
+
+    // The reconstruction by erosion of the negated images is the negated reconstruction by dilation
+    let (neg_seed, neg_mask) = (seed.mapv(|s: i32| -s), mask.mapv(|m| -m));
+    let reconstructed = reconstruction(&neg_seed, &neg_mask, &full, ReconstructionMethod::Erosion);
+    assert_eq!(reconstructed, gt.mapv(|g| -g));
+}
+
+#[test] // Results verified with the `binary_hit_or_miss` example from SciPy's documentation.
+fn test_binary_hit_or_miss() {
+    let mut mask = Array2::from_elem((7, 7), false);
+    mask[(1, 1)] = true;
+    mask.slice_mut(s![2..4, 2..4]).fill(true);
+    mask.slice_mut(s![4..6, 4..6]).fill(true);
+    let structure1 = arr2(&[[true, false, false], [false, true, true], [false, true, true]]);
+    let mut gt = Array2::from_elem((7, 7), false);
+    gt[(2, 2)] = true;
+    gt[(4, 4)] = true;
+    assert_eq!(binary_hit_or_miss(&mask, &structure1, None, &[0], None), gt);
+
+    // The end points of a line
+    let mut line = Array2::from_elem((5, 9), false);
+    line.slice_mut(s![2, 1..8]).fill(true);
+    let end = arr2(&[[false, false, false], [false, true, true], [false, false, false]]);
+    let background = arr2(&[[true, true, true], [true, false, false], [true, true, true]]);
+    let mut gt = Array2::from_elem((5, 9), false);
+    gt[(2, 1)] = true;
+    assert_eq!(binary_hit_or_miss(&line, &end, Some(&background), &[0], None), gt);
+    let mut gt = Array2::from_elem((5, 9), false);
+    gt[(2, 0)] = true;
+    assert_eq!(binary_hit_or_miss(&line, &end, Some(&background), &[0, -1], None), gt);
+
+    // A full `structure1` has an empty complement, so only `structure1` is searched
+    let mut squares = mask.clone();
+    squares.slice_mut(s![2..6, 2..6]).fill(true);
+    let mut gt = Array2::from_elem((7, 7), false);
+    gt.slice_mut(s![3..5, 3..5]).fill(true);
+    assert_eq!(
+        binary_hit_or_miss(&squares, &Array2::from_elem((3, 3), true), None, &[0], None),
+        gt
+    );
+
+    // A center-only `structure1` finds the isolated voxels
+    let center = arr2(&[[false, false, false], [false, true, false], [false, false, false]]);
+    let mut dots = Array2::from_elem((5, 5), false);
+    dots[(1, 1)] = true;
+    dots.slice_mut(s![3, 1..3]).fill(true);
+    let mut gt = Array2::from_elem((5, 5), false);
+    gt[(1, 1)] = true;
+    assert_eq!(binary_hit_or_miss(&dots, &center, None, &[0], None), gt);
+}
+
+#[test] // Results are logical.
+fn test_binary_hit_or_miss_nd() {
+    // Compare with a naive implementation on noise, including the borders
+    let mask = noise((5, 6, 7)).mapv(|v| v < 0.6);
+    let structure1 = Array3::from_shape_fn((3, 3, 3), |p| p == (1, 1, 1) || p == (0, 1, 2));
+    let structure2 = Array3::from_shape_fn((3, 1, 2), |p| p == (0, 0, 0) || p == (2, 0, 1));
+    let naive = |origin1: [isize; 3], origin2: [isize; 3]| {
+        let fits = |p: (usize, usize, usize), structure: &Array3<bool>, origin: [isize; 3], v| {
+            structure.indexed_iter().filter(|(_, &s)| s).all(|((i, j, k), _)| {
+                let shape = structure.shape();
+                let q = [
+                    p.0 as isize + i as isize - (shape[0] / 2) as isize - origin[0],
+                    p.1 as isize + j as isize - (shape[1] / 2) as isize - origin[1],
+                    p.2 as isize + k as isize - (shape[2] / 2) as isize - origin[2],
+                ];
+                let inside =
+                    q.iter().zip(mask.shape()).all(|(&q, &l)| (0..l as isize).contains(&q));
+                inside && mask[(q[0] as usize, q[1] as usize, q[2] as usize)] == v
+            })
+        };
+        Array3::from_shape_fn(mask.dim(), |p| {
+            fits(p, &structure1, origin1, true) && fits(p, &structure2, origin2, false)
+        })
+    };
+
+    let out = binary_hit_or_miss(&mask, &structure1, Some(&structure2), &[0], None);
+    assert_eq!(out, naive([0, 0, 0], [0, 0, 0]));
+    assert!(out.iter().any(|&o| o));
+    let out =
+        binary_hit_or_miss(&mask, &structure1, Some(&structure2), &[1, 0, -1], Some(&[-1, 0, -1]));
+    assert_eq!(out, naive([1, 0, -1], [-1, 0, -1]));
+
+    // Same results in Fortran order
+    let mut f_mask = Array3::from_elem((5, 6, 7).f(), false);
+    f_mask.assign(&mask);
+    let out = binary_hit_or_miss(&f_mask, &structure1, Some(&structure2), &[0], None);
+    assert_eq!(out, naive([0, 0, 0], [0, 0, 0]));
+}
+
+#[test] // Results are logical.
+fn test_skeletonize_3d() {
+    // The centerline of a tube
+    let mut tube = Mask::from_elem((9, 9, 20), false);
+    tube.slice_mut(s![2..7, 2..7, 2..18]).fill(true);
+    let skeleton = skeletonize_3d(&tube);
+    assert!(skeleton.iter().filter(|&&s| s).count() >= 10);
+    assert!((&skeleton & &!&tube).iter().all(|&s| !s));
+    let graph = skeleton_graph(&skeleton);
+    assert_eq!(graph.endpoints.len(), 2);
+    assert!(graph.branch_points.is_empty());
+    assert_eq!(graph.branches.len(), 1);
+    assert!(graph.branch_points.iter().chain(&graph.endpoints).all(|p| (2..7).contains(&p[0])));
+
+    // The topology is kept: 2 objects, one of them with a tunnel
+    let mut objects = tube.clone();
+    objects.slice_mut(s![1..8, 1..8, 0..2]).fill(true);
+    objects.slice_mut(s![3..6, 3..6, 0..2]).fill(false);
+    objects.slice_mut(s![.., .., 2..5]).fill(false);
+    let skeleton = skeletonize_3d(&objects);
+    let full = Kernel3d::Full.generate();
+    assert_eq!(label::<u16>(&skeleton, &full).1, 2);
+    let ring = skeleton.slice(s![.., .., 0..2]).to_owned();
+    let graph = skeleton_graph(&ring);
+    assert!(graph.endpoints.is_empty());
+    assert!(!graph.branches.is_empty());
+    assert!(graph.branches.iter().all(|b| b.voxels.first() == b.voxels.last()));
+
+    // A single voxel is kept
+    let mut single = Mask::from_elem((3, 3, 3), false);
+    single[(1, 1, 1)] = true;
+    assert_eq!(skeletonize_3d(&single), single);
+}
+
+#[test] // Results are logical.
+fn test_medial_axis() {
+    let mut rectangle = Array2::from_elem((9, 23), false);
+    rectangle.slice_mut(s![1..8, 1..22]).fill(true);
+    let (skeleton, distance) = medial_axis(&rectangle);
+    assert!(skeleton.slice(s![4, 4..19]).iter().all(|&s| s));
+    assert_eq!(distance[(4, 11)], 4.0);
+    assert!(skeleton.iter().zip(&distance).all(|(&s, &d)| s == (d > 0.0)));
+    let graph = skeleton_graph(&skeleton);
+    assert_eq!(graph.endpoints.len(), 4);
+    assert_eq!(graph.branch_points.len(), 2);
+
+    // The distance is the Euclidean distance to the closest background pixel
+    let mask = noise((12, 15)).mapv(|v| v < 0.8);
+    let (skeleton, distance) = medial_axis(&mask);
+    for ((i, j), &d) in distance.indexed_iter() {
+        let naive = mask
+            .indexed_iter()
+            .filter(|(_, &m)| !m)
+            .map(|((k, l), _)| ((i.abs_diff(k).pow(2) + j.abs_diff(l).pow(2)) as f64).sqrt())
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(d, if skeleton[(i, j)] { naive } else { 0.0 });
+    }
+}
+
+#[test] // Results are logical.
+fn test_skeleton_graph() {
+    // A T shape
+    let mut t = Array2::from_elem((9, 11), false);
+    t.slice_mut(s![2, 1..10]).fill(true);
+    t.slice_mut(s![3..8, 5]).fill(true);
+    let graph = skeleton_graph(&t);
+    assert_eq!(graph.branch_points, vec![vec![2, 5]]);
+    assert_eq!(graph.endpoints, vec![vec![2, 1], vec![2, 9], vec![7, 5]]);
+    let mut lengths: Vec<_> = graph.branches.iter().map(|b| b.length).collect();
+    lengths.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(lengths, vec![4.0, 4.0, 5.0]);
+    for branch in &graph.branches {
+        assert_eq!(branch.voxels.len(), branch.length as usize + 1);
+        assert!(branch.voxels.contains(&vec![2, 5]));
+    }
+
+    // A cycle without any branch point
+    let mut diamond = Array2::from_elem((7, 7), false);
+    for (i, j) in [(1, 3), (2, 2), (2, 4), (3, 1), (3, 5), (4, 2), (4, 4), (5, 3)] {
+        diamond[(i, j)] = true;
+    }
+    let graph = skeleton_graph(&diamond);
+    assert!(graph.branch_points.is_empty() && graph.endpoints.is_empty());
+    assert_eq!(graph.branches.len(), 1);
+    assert_eq!(graph.branches[0].voxels.len(), 9);
+    assert!((graph.branches[0].length - 8.0 * 2f64.sqrt()).abs() < 1e-12);
+
+    // 3D diagonal line
+    let mut line = Array3::from_elem((5, 5, 5), false);
+    for i in 0..5 {
+        line[(i, i, 4 - i)] = true;
+    }
+    let graph = skeleton_graph(&line);
+    assert_eq!(graph.endpoints, vec![vec![0, 0, 4], vec![4, 4, 0]]);
+    assert!((graph.branches[0].length - 4.0 * 3f64.sqrt()).abs() < 1e-12);
+}
+
+#[test] // Results verified with the `distance_transform_edt` example from SciPy's documentation.
+fn test_distance_transform_edt() {
+    let mask = arr2(&[
+        [0, 1, 1, 1, 1],
+        [0, 0, 1, 1, 1],
+        [0, 1, 1, 1, 1],
+        [0, 1, 1, 1, 0],
+        [0, 1, 1, 0, 0],
+    ])
+    .mapv(|m| m == 1);
+    let (r2, r5, r8, r13) = (2f64.sqrt(), 5f64.sqrt(), 8f64.sqrt(), 13f64.sqrt());
+    let gt = arr2(&[
+        [0.0, 1.0, r2, r5, 3.0],
+        [0.0, 0.0, 1.0, 2.0, 2.0],
+        [0.0, 1.0, r2, r2, 1.0],
+        [0.0, 1.0, r2, 1.0, 0.0],
+        [0.0, 1.0, 1.0, 0.0, 0.0],
+    ]);
+    let (distance, indices) = distance_transform_edt(&mask, &[1.0], false);
+    assert!(indices.is_none());
+    assert!(distance.iter().zip(&gt).all(|(d, g)| (d - g).abs() < 1e-12));
+
+    let gt = arr2(&[
+        [0.0, 1.0, 2.0, r8, r13],
+        [0.0, 0.0, 1.0, 2.0, 3.0],
+        [0.0, 1.0, 2.0, r5, 2.0],
+        [0.0, 1.0, 2.0, 1.0, 0.0],
+        [0.0, 1.0, 1.0, 0.0, 0.0],
+    ]);
+    let (distance, indices) = distance_transform_edt(&mask, &[2.0, 1.0], true);
+    assert!(distance.iter().zip(&gt).all(|(d, g)| (d - g).abs() < 1e-12));
+    let indices = indices.unwrap();
+    assert_eq!(indices.shape(), &[2, 5, 5]);
+    for ((i, j), &d) in distance.indexed_iter() {
+        let (k, l) = (indices[(0, i, j)], indices[(1, i, j)]);
+        assert!(!mask[(k, l)]);
+        let feature_distance =
+            ((2.0 * (i as f64 - k as f64)).powi(2) + (j as f64 - l as f64).powi(2)).sqrt();
+        assert!((feature_distance - d).abs() < 1e-12);
+    }
+}
+
+#[test] // Results are logical.
+fn test_distance_transform_edt_nd() {
+    // Compare with a naive implementation on noise, with an anisotropic sampling
+    let mask = noise((6, 7, 8)).mapv(|v| v < 0.9);
+    let sampling = [1.5, 1.0, 0.5];
+    let naive = |mask: &Array3<bool>| {
+        Array3::from_shape_fn(mask.dim(), |(i, j, k)| {
+            mask.indexed_iter()
+                .filter(|(_, &m)| !m)
+                .map(|((a, b, c), _)| {
+                    let d = [i as f64 - a as f64, j as f64 - b as f64, k as f64 - c as f64];
+                    d.iter().zip(&sampling).map(|(d, s)| (d * s).powi(2)).sum::<f64>().sqrt()
+                })
+                .fold(f64::INFINITY, f64::min)
+        })
+    };
+    let gt = naive(&mask);
+    let (distance, indices) = distance_transform_edt(&mask, &sampling, true);
+    assert!(distance.iter().zip(&gt).all(|(d, g)| (d - g).abs() < 1e-12));
+    let indices = indices.unwrap();
+    for ((i, j, k), &d) in distance.indexed_iter() {
+        let feature = [indices[(0, i, j, k)], indices[(1, i, j, k)], indices[(2, i, j, k)]];
+        assert!(!mask[feature]);
+        let d2: f64 = [i, j, k]
+            .iter()
+            .zip(&feature)
+            .zip(&sampling)
+            .map(|((&a, &b), s)| ((a as f64 - b as f64) * s).powi(2))
+            .sum();
+        assert!((d2.sqrt() - d).abs() < 1e-12);
+    }
+
+    // Same results in Fortran order
+    let mut f_mask = Array3::from_elem((6, 7, 8).f(), false);
+    f_mask.assign(&mask);
+    let (f_distance, f_indices) = distance_transform_edt(&f_mask, &sampling, true);
+    assert_eq!(f_distance, distance);
+    assert_eq!(f_indices.unwrap(), indices);
+
+    // Without background, the distance is infinite
+    let full = Array2::from_elem((3, 4), true);
+    let (distance, indices) = distance_transform_edt(&full, &[1.0], true);
+    assert!(distance.iter().all(|d| d.is_infinite()));
+    assert!(indices.unwrap().iter().all(|&i| i == usize::MAX));
+}
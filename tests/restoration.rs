@@ -0,0 +1,138 @@
+use approx::assert_relative_eq;
+use ndarray::{s, Array2, Array3};
+
+use ndarray_ndimage::{
+    convolve, denoise_tv, denoise_tv_bregman, richardson_lucy, wiener, BorderMode,
+};
+
+mod common;
+use common::noise;
+
+fn noisy_step() -> (Array3<f64>, Array3<f64>) {
+    let clean = Array3::from_shape_fn((8, 9, 10), |(_, y, _)| if y < 4 { 1.0 } else { 3.0 });
+    let noisy = &clean + noise(clean.dim()) - 0.5;
+    (clean, noisy)
+}
+
+fn error(a: &Array3<f64>, b: &Array3<f64>) -> f64 {
+    (a - b).mapv(|v| v * v).mean().unwrap()
+}
+
+#[test] // Results are logical. A constant image can't be denoised.
+fn test_denoise_tv_constant() {
+    let a = Array2::from_elem((6, 7), 4.2);
+    assert_relative_eq!(denoise_tv(&a, 0.1, 200, 2e-4, &[1.0]), a, epsilon = 1e-10);
+    assert_relative_eq!(denoise_tv_bregman(&a, 5.0, 100, 1e-3), a, epsilon = 1e-10);
+
+    let a = Array3::from_elem((4, 5, 6), -1.5);
+    assert_relative_eq!(denoise_tv(&a, 0.1, 200, 2e-4, &[1.0, 2.0, 0.5]), a, epsilon = 1e-10);
+    assert_relative_eq!(denoise_tv_bregman(&a.view(), 5.0, 100, 1e-3), a, epsilon = 1e-10);
+}
+
+#[test] // Results are logical. The noise must be reduced and the edge preserved.
+fn test_denoise_tv() {
+    let (clean, noisy) = noisy_step();
+
+    let denoised = denoise_tv(&noisy, 0.2, 200, 2e-4, &[1.0]);
+    assert!(error(&denoised, &clean) < error(&noisy, &clean) / 3.0);
+    assert!(denoised.slice(s![.., ..3, ..]).iter().all(|&v| v < 1.5));
+    assert!(denoised.slice(s![.., 5.., ..]).iter().all(|&v| v > 2.5));
+
+    // The mean intensity is preserved
+    assert_relative_eq!(denoised.mean().unwrap(), noisy.mean().unwrap(), epsilon = 1e-10);
+
+    // A greater weight smooths more
+    let smoother = denoise_tv(&noisy, 0.5, 200, 2e-4, &[1.0]);
+    let variation = |a: &Array3<f64>| {
+        (&a.slice(s![1.., .., ..]) - &a.slice(s![..-1, .., ..])).mapv(f64::abs).sum()
+    };
+    assert!(variation(&smoother) < variation(&denoised));
+
+    // A greater spacing means smaller gradients, thus less smoothing
+    let spaced = denoise_tv(&noisy, 0.2, 200, 2e-4, &[4.0]);
+    assert!(error(&spaced, &noisy) < error(&denoised, &noisy));
+}
+
+#[test] // Results are logical. The noise must be reduced and the edge preserved.
+fn test_denoise_tv_bregman() {
+    let (clean, noisy) = noisy_step();
+
+    let denoised = denoise_tv_bregman(&noisy, 4.0, 100, 1e-4);
+    assert!(error(&denoised, &clean) < error(&noisy, &clean) / 3.0);
+    assert!(denoised.slice(s![.., ..3, ..]).iter().all(|&v| v < 1.5));
+    assert!(denoised.slice(s![.., 5.., ..]).iter().all(|&v| v > 2.5));
+
+    // A greater weight keeps the result closer to the input
+    let closer = denoise_tv_bregman(&noisy, 20.0, 100, 1e-4);
+    assert!(error(&closer, &noisy) < error(&denoised, &noisy));
+}
+
+#[should_panic]
+#[test]
+fn test_denoise_tv_bad_spacing() {
+    let a = Array3::from_elem((4, 5, 6), 1.0);
+    let _ = denoise_tv(&a, 0.1, 10, 2e-4, &[1.0, 1.0]);
+}
+
+fn gaussian_psf(sigma: f64) -> Array2<f64> {
+    let psf = Array2::from_shape_fn((7, 7), |(x, y)| {
+        let d2 = (x as f64 - 3.0).powi(2) + (y as f64 - 3.0).powi(2);
+        (-d2 / (2.0 * sigma * sigma)).exp()
+    });
+    let sum = psf.sum();
+    psf / sum
+}
+
+fn spots() -> Array2<f64> {
+    let mut a = Array2::zeros((24, 25));
+    a.slice_mut(s![6..8, 6..9]).fill(1.0);
+    a[(15, 16)] = 2.0;
+    a[(16, 8)] = 1.5;
+    a
+}
+
+#[test] // Results are logical. The deconvolution must be closer to the original than the blurred image.
+fn test_wiener() {
+    let original = spots();
+    let psf = gaussian_psf(1.2);
+    let blurred = convolve(&original, &psf, BorderMode::Constant(0.0), 0);
+
+    let restored = wiener(&blurred, &psf, 1e-4);
+    let blurred_error = (&blurred - &original).mapv(|v| v * v).mean().unwrap();
+    let restored_error = (&restored - &original).mapv(|v| v * v).mean().unwrap();
+    assert!(restored_error < blurred_error / 1.5);
+    assert!(restored[(15, 16)] > blurred[(15, 16)] * 2.0);
+
+    // More regularization gives a smoother result
+    let smooth = wiener(&blurred, &psf, 1.0);
+    assert!(smooth[(15, 16)] < restored[(15, 16)]);
+
+    // A PSF with even lengths is centered
+    let a = Array3::from_shape_fn((6, 7, 8), |(x, y, z)| (x + y * z) as f64);
+    assert_relative_eq!(wiener(&a, &Array3::from_elem((1, 1, 1), 1.0), 0.0), a, epsilon = 1e-8);
+    let mut centered = Array3::zeros((3, 3, 3));
+    centered[(0, 1, 1)] = 0.5;
+    centered[(1, 1, 1)] = 0.5;
+    let blurred = convolve(&a, &centered, BorderMode::Constant(0.0), 0);
+    let psf = Array3::from_elem((2, 1, 1), 0.5);
+    assert_relative_eq!(wiener(&blurred, &psf, 0.0), a, epsilon = 1e-6);
+}
+
+#[test] // Results are logical. The deconvolution must be closer to the original than the blurred image.
+fn test_richardson_lucy() {
+    let original = spots() + 0.1;
+    let psf = gaussian_psf(1.2);
+    let blurred = convolve(&original, &psf, BorderMode::Reflect, 0);
+
+    let restored = richardson_lucy(&blurred, &psf, 50, false);
+    let blurred_error = (&blurred - &original).mapv(|v| v * v).mean().unwrap();
+    let restored_error = (&restored - &original).mapv(|v| v * v).mean().unwrap();
+    assert!(restored_error < blurred_error / 2.0);
+    assert!(restored[(15, 16)] > blurred[(15, 16)] * 2.0);
+    assert!(restored.iter().all(|&v| v >= 0.0));
+    assert_relative_eq!(restored.sum(), blurred.sum(), max_relative = 1e-3);
+
+    let clipped = richardson_lucy(&blurred, &psf, 50, true);
+    assert!(restored.iter().any(|&v| v > 1.0));
+    assert_eq!(clipped, restored.mapv(|v| v.min(1.0)));
+}
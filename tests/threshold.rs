@@ -0,0 +1,240 @@
+use approx::assert_relative_eq;
+use ndarray::{arr1, s, Array1, Array2, Array3, Zip};
+
+use ndarray_ndimage::{
+    threshold_isodata, threshold_li, threshold_local, threshold_mean, threshold_multiotsu,
+    threshold_niblack, threshold_otsu, threshold_sauvola, threshold_triangle, threshold_yen,
+    BorderMode, LocalThresholdMethod,
+};
+
+/// Clusters of values evenly spread around the `centers`, with `counts` voxels each.
+fn clusters(centers: &[f64], counts: &[usize]) -> Array1<f64> {
+    centers
+        .iter()
+        .zip(counts)
+        .flat_map(|(&c, &n)| (0..n).map(move |i| c + (i as f64 + 0.5) / n as f64 - 0.5))
+        .collect()
+}
+
+/// Bright squares on a background with an intensity gradient.
+fn uneven_squares() -> (Array2<f64>, Array2<bool>) {
+    let mut truth = Array2::from_elem((30, 32), false);
+    for &(x, y) in &[(3, 3), (3, 20), (18, 8), (20, 24)] {
+        truth.slice_mut(s![x..x + 5, y..y + 5]).fill(true);
+    }
+    let data = Array2::from_shape_fn(truth.dim(), |(x, y)| {
+        let background = 0.1 * (x + y) as f64;
+        background + if truth[(x, y)] { 2.0 } else { 0.0 }
+    });
+    (data, truth)
+}
+
+#[test] // Results are logical. A global threshold can't separate the squares from the background.
+fn test_threshold_local() {
+    let (data, truth) = uneven_squares();
+    assert!(data.iter().zip(&truth).any(|(&v, &t)| !t && v > 3.0));
+    for method in [LocalThresholdMethod::Gaussian, LocalThresholdMethod::Mean] {
+        let thresholds = threshold_local(&data, 11, method, -0.4, BorderMode::Reflect);
+        let mask = Zip::from(&data).and(&thresholds).map_collect(|&v, &t| v > t);
+        assert_eq!(mask, truth);
+    }
+
+    let a = Array2::from_elem((7, 8), 1.5);
+    for method in
+        [LocalThresholdMethod::Gaussian, LocalThresholdMethod::Mean, LocalThresholdMethod::Median]
+    {
+        let thresholds = threshold_local(&a, 5, method, 0.5, BorderMode::Nearest);
+        assert_relative_eq!(thresholds, Array2::from_elem((7, 8), 1.0), epsilon = 1e-12);
+    }
+}
+
+#[test] // Results are logical.
+fn test_threshold_local_median() {
+    let a: Array1<f64> = arr1(&[1.0, 5.0, 2.0, 8.0, 3.0]);
+    assert_eq!(
+        threshold_local(&a, 3, LocalThresholdMethod::Median, 0.0, BorderMode::Reflect),
+        arr1(&[1.0, 2.0, 5.0, 3.0, 3.0])
+    );
+    assert_eq!(
+        threshold_local(&a, 3, LocalThresholdMethod::Median, 1.0, BorderMode::Constant(9.0)),
+        arr1(&[4.0, 1.0, 4.0, 2.0, 7.0])
+    );
+}
+
+#[should_panic]
+#[test]
+fn test_threshold_local_even() {
+    let a = Array2::<f64>::zeros((7, 8));
+    let _ = threshold_local(&a, 4, LocalThresholdMethod::Mean, 0.0, BorderMode::Reflect);
+}
+
+#[test] // Results are logical.
+fn test_threshold_niblack_sauvola() {
+    let a = Array2::from_elem((7, 8), 2.0);
+    assert_relative_eq!(threshold_niblack(&a, 3, 0.2), a, epsilon = 1e-12);
+    assert_relative_eq!(
+        threshold_sauvola(&a, 3, 0.2, Some(1.0)),
+        a.mapv(|v| v * 0.8),
+        epsilon = 1e-12
+    );
+
+    // Alternating values of 1 and 3, the local standard deviation is close to 1 everywhere
+    let a = Array2::from_shape_fn((9, 9), |(x, y)| if (x + y) % 2 == 0 { 1.0 } else { 3.0 });
+    let niblack = threshold_niblack(&a, 3, 0.5);
+    let sauvola = threshold_sauvola(&a, 3, 0.5, None);
+    let center = (4, 4);
+    // 5 values of 1 and 4 values of 3 in the window
+    let mean = 17.0 / 9.0;
+    let std = 80.0f64.sqrt() / 9.0;
+    assert_relative_eq!(niblack[center], mean - 0.5 * std, epsilon = 1e-12);
+    assert_relative_eq!(sauvola[center], mean * (1.0 + 0.5 * (std - 1.0)), epsilon = 1e-12);
+
+    // The border is mirrored, like scikit-image: `[1, 2, 4] -> [2, 1, 2, 4, 2]`
+    let a = arr1(&[1.0, 2.0, 4.0]);
+    assert_relative_eq!(
+        threshold_niblack(&a, 3, 0.0),
+        arr1(&[5.0, 7.0, 8.0]) / 3.0,
+        epsilon = 1e-12
+    );
+
+    // Dark squares on a bright background, like text on a document
+    let (data, truth) = uneven_squares();
+    let data = data.mapv(|v| 8.0 - v);
+    let niblack = threshold_niblack(&data, 11, 0.2);
+    let mask = Zip::from(&data).and(&niblack).map_collect(|&v, &t| v <= t);
+    assert!(mask.iter().zip(&truth).all(|(&m, &t)| m || !t));
+    let sauvola = threshold_sauvola(&data, 11, 0.2, None);
+    let mask = Zip::from(&data).and(&sauvola).map_collect(|&v, &t| v <= t);
+    assert_eq!(mask, truth);
+}
+
+#[test] // Results are logical. The thresholds must separate the clusters.
+fn test_threshold_global() {
+    let a = clusters(&[1.0, 5.0], &[300, 200]);
+    let otsu = threshold_otsu(&a, 256, None);
+    for t in [
+        otsu,
+        threshold_li(&a, None),
+        threshold_yen(&a, 256, None),
+        threshold_isodata(&a, 256, None),
+        threshold_mean(&a, None),
+    ] {
+        // The thresholds are bin centers, so they can be slightly inside a cluster
+        assert!(t > 1.45 && t < 4.55);
+    }
+    assert_eq!(threshold_multiotsu(&a, 2, 256, None), vec![otsu]);
+
+    // The triangle method needs a small foreground peak
+    let a = clusters(&[1.0, 5.0], &[900, 30]);
+    let triangle = threshold_triangle(&a, 256, None);
+    assert!(triangle > 1.45 && triangle < 4.55);
+    let triangle = threshold_triangle(&a.mapv(|v| -v), 256, None);
+    assert!(triangle < -1.45 && triangle > -4.55);
+
+    let a = Array2::from_elem((4, 5), 2.5);
+    assert_eq!(threshold_otsu(&a, 256, None), 2.5);
+    assert_eq!(threshold_li(&a, None), 2.5);
+    assert_eq!(threshold_triangle(&a, 256, None), 2.5);
+}
+
+#[test] // Results are logical. The integer types give the same thresholds as their `f64` values.
+fn test_threshold_global_integers() {
+    let a = clusters(&[1.0, 5.0], &[300, 200]);
+    let check = |a: &Array1<f64>, low: f64, high: f64| {
+        let thresholds = [
+            threshold_otsu(a, 256, None),
+            threshold_li(a, None),
+            threshold_yen(a, 256, None),
+            threshold_isodata(a, 256, None),
+            threshold_mean(a, None),
+        ];
+        // The thresholds are bin centers, so they can be slightly inside a cluster
+        for &t in &thresholds {
+            assert!(t > low && t < high);
+        }
+        thresholds
+    };
+
+    let a_u8 = a.mapv(|v| (v * 40.0).round() as u8);
+    let a_f64 = a_u8.mapv(|v| v as f64);
+    let thresholds = check(&a_f64, 58.0, 182.0);
+    assert_eq!(threshold_otsu(&a_u8, 256, None), thresholds[0]);
+    assert_eq!(threshold_li(&a_u8, None), thresholds[1]);
+    assert_eq!(threshold_yen(&a_u8, 256, None), thresholds[2]);
+    assert_eq!(threshold_isodata(&a_u8, 256, None), thresholds[3]);
+    assert_eq!(threshold_mean(&a_u8, None), thresholds[4]);
+    assert_eq!(threshold_multiotsu(&a_u8, 2, 256, None), vec![thresholds[0]]);
+
+    let a_u16 = a.mapv(|v| (v * 10_000.0).round() as u16);
+    let a_f64 = a_u16.mapv(|v| v as f64);
+    let thresholds = check(&a_f64, 14_500.0, 45_500.0);
+    assert_eq!(threshold_otsu(&a_u16, 256, None), thresholds[0]);
+    assert_eq!(threshold_li(&a_u16, None), thresholds[1]);
+    assert_eq!(threshold_yen(&a_u16, 256, None), thresholds[2]);
+    assert_eq!(threshold_isodata(&a_u16, 256, None), thresholds[3]);
+    assert_eq!(threshold_mean(&a_u16, None), thresholds[4]);
+
+    let a = clusters(&[1.0, 5.0], &[900, 30]).mapv(|v| (v * 40.0).round() as u8);
+    assert_eq!(
+        threshold_triangle(&a, 256, None),
+        threshold_triangle(&a.mapv(f64::from), 256, None)
+    );
+}
+
+#[test] // Results verified with scikit-image
+fn test_threshold_global_values() {
+    let a = arr1(&[
+        0.09, 1.0, 0.09, 1.0, 0.09, 1.0, 0.09, 1.0, 0.09, 1.0, 0.09, 1.0, 0.09, 1.0, 3.74, 3.35,
+        4.26, 3.87, 3.48, 4.39, 4.0, 3.61, 4.52, 4.13, 8.11, 8.24, 8.37, 8.5, 8.63, 8.76,
+    ]);
+    assert_relative_eq!(threshold_otsu(&a, 256, None), 4.50966796875, epsilon = 1e-9);
+    assert_relative_eq!(threshold_yen(&a, 256, None), 3.86619140625, epsilon = 1e-9);
+    assert_relative_eq!(threshold_li(&a, None), 2.1225466436248754, epsilon = 1e-9);
+    assert_relative_eq!(threshold_isodata(&a, 256, None), 3.05337890625, epsilon = 1e-9);
+    assert_relative_eq!(threshold_triangle(&a, 256, None), 0.14080078125, epsilon = 1e-9);
+    assert_relative_eq!(
+        &threshold_multiotsu(&a, 3, 256, None)[..],
+        &[0.98748046875, 4.50966796875][..],
+        epsilon = 1e-9
+    );
+
+    let a = Array1::linspace(-127.0, 0.0, 256);
+    assert_relative_eq!(threshold_isodata(&a, 256, None), -63.748046875, epsilon = 1e-9);
+}
+
+#[test] // Results are logical.
+fn test_threshold_multiotsu() {
+    let a = clusters(&[1.0, 5.0, 9.0, 14.0], &[100, 300, 200, 100]);
+    let thresholds = threshold_multiotsu(&a, 4, 128, None);
+    assert_eq!(thresholds.len(), 3);
+    assert!(thresholds[0] > 1.45 && thresholds[0] < 4.55);
+    assert!(thresholds[1] > 5.45 && thresholds[1] < 8.55);
+    assert!(thresholds[2] > 9.45 && thresholds[2] < 13.55);
+
+    // The thresholds are bin centers
+    let a = arr1(&[0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0]);
+    let thresholds = threshold_multiotsu(&a, 3, 3, None);
+    assert_relative_eq!(&thresholds[..], &[0.5, 1.5][..], epsilon = 1e-12);
+}
+
+#[test] // Results are logical. Only the voxels in the mask are used.
+fn test_threshold_mask() {
+    let a = Array3::from_shape_fn((10, 11, 12), |(x, y, _)| match (x < 5, y < 5) {
+        (true, true) => 1.0,
+        (true, false) => 3.0,
+        (false, _) => 10.0,
+    });
+    let mask = Array3::from_shape_fn(a.dim(), |(x, _, _)| x < 5);
+    let t = threshold_otsu(&a, 64, Some(&mask));
+    assert!(t > 1.0 && t < 3.0);
+    let t = threshold_otsu(&a, 64, None);
+    assert!(t > 3.0 && t < 10.0);
+    assert_relative_eq!(threshold_mean(&a, Some(&mask)), (300.0 + 3.0 * 360.0) / 660.0);
+}
+
+#[should_panic]
+#[test]
+fn test_threshold_empty_mask() {
+    let a = Array2::<f64>::zeros((4, 5));
+    let _ = threshold_otsu(&a, 256, Some(&Array2::from_elem((4, 5), false)));
+}